@@ -0,0 +1,51 @@
+//! Parses documents of doubling size and reports the time per byte, which
+//! should stay roughly constant if parsing scales linearly with input size.
+
+use lite_json::parse_json;
+use std::time::{Duration, Instant};
+
+fn document(entries: usize) -> String {
+    let mut json = String::from("[");
+    for i in 0..entries {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            r#"{{"id": {}, "name": "entry {}", "value": -12.5e3, "tags": ["a", "b"], "ok": true}}"#,
+            i, i
+        ));
+    }
+    json.push(']');
+    json
+}
+
+fn measure(input: &str) -> Duration {
+    let iterations = 5;
+    let start = Instant::now();
+    for _ in 0..iterations {
+        parse_json(input).expect("benchmark document is valid");
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    let mut baseline = None;
+    for &entries in &[500, 1_000, 2_000, 4_000, 8_000] {
+        let input = document(entries);
+        let elapsed = measure(&input);
+        let per_byte = elapsed.as_nanos() as f64 / input.len() as f64;
+        let ratio = per_byte / *baseline.get_or_insert(per_byte);
+        println!(
+            "{:>8} bytes: {:>10.3?} ({:.2} ns/byte, {:.2}x baseline)",
+            input.len(),
+            elapsed,
+            per_byte,
+            ratio
+        );
+        assert!(
+            ratio < 4.0,
+            "time per byte grew {:.2}x, parsing is not linear",
+            ratio
+        );
+    }
+}
@@ -4,12 +4,14 @@ use crate::traits::{Error, Position};
 extern crate alloc;
 
 #[cfg(not(feature = "std"))]
-use alloc::fmt::Formatter;
-#[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use core::fmt::{self, Display, Formatter, Write};
 
-#[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Copy, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub struct SimplePosition {
     pub index: u32,
     pub line: u32,
@@ -53,20 +55,136 @@ impl core::ops::Sub<Self> for SimplePosition {
     }
 }
 
-#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
-pub struct SimpleError {
-    pub reasons: Vec<(SimplePosition, &'static str)>,
+/// Position within a byte buffer, tracking the byte offset alongside the
+/// character based `SimplePosition` so that seeking is constant time.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct BytePosition {
+    pub offset: u32,
+    pub position: SimplePosition,
 }
 
-#[cfg(not(feature = "std"))]
-impl core::fmt::Debug for SimpleError {
-    fn fmt(&self, _f: &mut Formatter<'_>) -> core::fmt::Result {
+impl BytePosition {
+    pub fn next(&self, c: char) -> Self {
+        Self {
+            offset: self.offset + c.len_utf8() as u32,
+            position: self.position.next(c),
+        }
+    }
+}
+
+impl Position for BytePosition {
+    fn index(&self) -> u32 {
+        self.position.index
+    }
+
+    fn line(&self) -> u32 {
+        self.position.line
+    }
+
+    fn column(&self) -> u32 {
+        self.position.column
+    }
+}
+
+impl core::ops::Sub<Self> for BytePosition {
+    type Output = i32;
+
+    fn sub(self, rhs: BytePosition) -> Self::Output {
+        self.position - rhs.position
+    }
+}
+
+impl From<BytePosition> for SimplePosition {
+    fn from(pos: BytePosition) -> Self {
+        pos.position
+    }
+}
+
+#[cfg_attr(feature = "std", derive(PartialEq, Eq))]
+#[derive(Debug, Clone)]
+pub struct SimpleError<P = SimplePosition> {
+    pub reasons: Vec<(P, &'static str)>,
+}
+
+impl<P: Position> SimpleError<P> {
+    /// Renders the error followed by the offending line of `source` with a
+    /// caret under the column where parsing failed.
+    pub fn render(&self, source: &str) -> String {
+        match self.reasons.first() {
+            Some((pos, _)) => render_snippet(self, source, pos.line(), pos.column()),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl<P: Position> Display for SimpleError<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut reasons = self.reasons.iter();
+        match reasons.next() {
+            Some((pos, reason)) => write!(
+                f,
+                "{} at line {}, column {}",
+                reason,
+                pos.line() + 1,
+                pos.column() + 1
+            )?,
+            None => return f.write_str("parse error"),
+        }
+        for (_, reason) in reasons {
+            write!(f, ", in {}", reason)?;
+        }
         Ok(())
     }
 }
 
-impl Error for SimpleError {
-    type Position = SimplePosition;
+#[cfg(feature = "std")]
+impl<P: Position + fmt::Debug> std::error::Error for SimpleError<P> {}
+
+/// Formats `message` followed by line `line` of `source` and a caret under
+/// `column`, both zero based, in the style of compiler diagnostics:
+///
+/// ```text
+/// error: Value at line 2, column 8
+///   |
+/// 2 |   "a": ]
+///   |        ^
+/// ```
+pub fn render_snippet(message: &dyn Display, source: &str, line: u32, column: u32) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "error: {}", message);
+    let text = source
+        .split('\n')
+        .nth(line as usize)
+        .unwrap_or("")
+        .trim_end_matches('\r');
+    let number = (line + 1).to_string();
+    let gutter = " ".repeat(number.len());
+    let _ = writeln!(out, "{} |", gutter);
+    let _ = writeln!(out, "{} | {}", number, text);
+    // Keep tabs so the caret lines up with the source however tabs are shown.
+    let padding: String = text
+        .chars()
+        .take(column as usize)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let _ = write!(out, "{} | {}^", gutter, padding);
+    out
+}
+
+impl From<SimpleError<BytePosition>> for SimpleError {
+    fn from(error: SimpleError<BytePosition>) -> Self {
+        Self {
+            reasons: error
+                .reasons
+                .into_iter()
+                .map(|(pos, reason)| (pos.into(), reason))
+                .collect(),
+        }
+    }
+}
+
+impl<P> Error for SimpleError<P> {
+    type Position = P;
 
     fn reasons(&self) -> &[(Self::Position, &'static str)] {
         &self.reasons[..]
@@ -78,3 +196,35 @@ impl Error for SimpleError {
         Self { reasons }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_position_counts_bytes_and_chars() {
+        let pos = BytePosition::default().next('日').next('\n').next('a');
+        assert_eq!(pos.offset, 5);
+        assert_eq!(
+            SimplePosition::from(pos),
+            SimplePosition {
+                index: 3,
+                line: 1,
+                column: 1
+            }
+        );
+    }
+
+    #[test]
+    fn it_renders_snippets() {
+        let source = "{\r\n\t\"a\": ]\r\n}";
+        assert_eq!(
+            render_snippet(&"Value at line 2, column 7", source, 1, 6),
+            "error: Value at line 2, column 7\n  |\n2 | \t\"a\": ]\n  | \t     ^"
+        );
+        assert_eq!(
+            render_snippet(&"Eof", "x", 9, 0),
+            "error: Eof\n   |\n10 | \n   | ^"
+        );
+    }
+}
@@ -1,22 +1,49 @@
 #[cfg(not(feature = "std"))]
 pub extern crate alloc;
 
-use crate::impls::{SimpleError, SimplePosition};
+use crate::impls::{BytePosition, SimpleError, SimplePosition};
 use crate::traits::{Error, Input, Position, ResultOf};
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 use core::marker::PhantomData;
 
+/// How to decode a `\u` escape of a UTF-16 surrogate that is not part of a pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoneSurrogate {
+    /// Reject the input.
+    Error,
+    /// Decode it as U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+    /// Keep it, encoded as WTF-8, in outputs that can hold it. A surrogate code point
+    /// is not valid UTF-8, so outputs of `str` decode it as with `Replace`.
+    Preserve,
+}
+
+/// What to do with a number whose integer digits or exponent do not fit into
+/// `NumberValue`. Fraction digits that do not fit are always kept as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberOverflow {
+    /// Reject the input.
+    Error,
+    /// Keep the number's text, as for fraction digits that do not fit, so it
+    /// still converts and serializes exactly.
+    KeepText,
+}
+
 #[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
 #[derive(Clone)]
 pub struct ParserOptions {
     pub max_nest_level: Option<u32>,
+    pub lone_surrogate: LoneSurrogate,
+    pub number_overflow: NumberOverflow,
 }
 
 impl Default for ParserOptions {
     fn default() -> Self {
         ParserOptions {
             max_nest_level: Some(100),
+            lone_surrogate: LoneSurrogate::Error,
+            number_overflow: NumberOverflow::Error,
         }
     }
 }
@@ -52,6 +79,16 @@ impl ParserContext {
     }
 }
 
+/// Returns whichever of the two errors got further into the input, preferring `a`.
+fn deeper<I: Input>(a: I::Error, b: I::Error) -> I::Error {
+    let index = |e: &I::Error| e.reasons().first().map(|(pos, _)| pos.index());
+    if index(&b) > index(&a) {
+        b
+    } else {
+        a
+    }
+}
+
 pub trait Parser<I: Input> {
     type Output;
     fn parse(input: &I, current: I::Position, context: &ParserContext)
@@ -133,8 +170,10 @@ impl<I: Input, P: Parser<I>, P2: Parser<I>> Parser<I> for OneOf<P, P2> {
     ) -> ResultOf<I, Self::Output> {
         P::parse(input, current, context)
             .map(|(output, pos)| (Either::A(output), pos))
-            .or_else(|_| {
-                P2::parse(input, current, context).map(|(output, pos)| (Either::B(output), pos))
+            .or_else(|e| {
+                P2::parse(input, current, context)
+                    .map(|(output, pos)| (Either::B(output), pos))
+                    .map_err(|e2| deeper::<I>(e, e2))
             })
             .map_err(|e| e.add_reason(current, "OneOf"))
     }
@@ -168,11 +207,12 @@ impl<I: Input, P: Parser<I>> Parser<I> for OneOrMore<P> {
             P::parse(input, current, context).map_err(|e| e.add_reason(current, "OneOrMore"))?;
         output_list.push(output);
         loop {
-            if let Ok((output, next_pos)) = P::parse(input, pos, context) {
-                pos = next_pos;
-                output_list.push(output);
-            } else {
-                return Ok((output_list, pos));
+            match P::parse(input, pos, context) {
+                Ok((output, next_pos)) => {
+                    pos = next_pos;
+                    output_list.push(output);
+                }
+                Err(_) => return Ok((output_list, pos)),
             }
         }
     }
@@ -208,9 +248,9 @@ impl Input for &str {
     }
 
     fn error_at(&self, pos: Self::Position, reason: &'static str) -> Self::Error {
-        let mut reasons = Vec::new();
-        reasons.push((pos, reason));
-        SimpleError { reasons }
+        SimpleError {
+            reasons: vec![(pos, reason)],
+        }
     }
 
     fn is_end(&self, pos: Self::Position) -> bool {
@@ -218,6 +258,66 @@ impl Input for &str {
     }
 }
 
+fn utf8_char_width(first: u8) -> usize {
+    match first {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 0,
+    }
+}
+
+impl Input for &[u8] {
+    type Position = BytePosition;
+    type Error = SimpleError<BytePosition>;
+
+    fn next(&self, pos: Self::Position) -> Result<(char, Self::Position), Self::Error> {
+        let offset = pos.offset as usize;
+        let first = *self
+            .get(offset)
+            .ok_or_else(|| self.error_at(pos, "Out of bounds"))?;
+        if first < 0x80 {
+            let c = first as char;
+            return Ok((c, pos.next(c)));
+        }
+        let bytes = self
+            .get(offset..offset + utf8_char_width(first))
+            .ok_or_else(|| self.error_at(pos, "Out of bounds"))?;
+        core::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| s.chars().next())
+            .ok_or_else(|| self.error_at(pos, "Invalid UTF-8"))
+            .map(|c| (c, pos.next(c)))
+    }
+
+    fn next_range(
+        &self,
+        start: Self::Position,
+        counts: u32,
+    ) -> Result<(&str, Self::Position), Self::Error> {
+        let mut pos = start;
+        for _ in 0..counts {
+            let (_, next) = self.next(pos)?;
+            pos = next;
+        }
+        let bytes = &self[start.offset as usize..pos.offset as usize];
+        core::str::from_utf8(bytes)
+            .map(|s| (s, pos))
+            .map_err(|_| self.error_at(start, "Invalid UTF-8"))
+    }
+
+    fn error_at(&self, pos: Self::Position, reason: &'static str) -> Self::Error {
+        SimpleError {
+            reasons: vec![(pos, reason)],
+        }
+    }
+
+    fn is_end(&self, pos: Self::Position) -> bool {
+        pos.offset as usize >= self.len()
+    }
+}
+
 #[macro_export]
 macro_rules! literals {
     (
@@ -278,3 +378,95 @@ macro_rules! parsers {
         )*
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(input: &[u8], chars: u32) -> BytePosition {
+        let mut pos = BytePosition::default();
+        for _ in 0..chars {
+            pos = input.next(pos).unwrap().1;
+        }
+        pos
+    }
+
+    #[test]
+    fn bytes_decode_multi_byte_characters() {
+        let input = "aé日😀".as_bytes();
+        let mut pos = BytePosition::default();
+        let mut offsets = Vec::new();
+        while !input.is_end(pos) {
+            let (c, next) = input.next(pos).unwrap();
+            offsets.push((c, next.offset, next.position.index));
+            pos = next;
+        }
+        assert_eq!(
+            offsets,
+            [('a', 1, 1), ('é', 3, 2), ('日', 6, 3), ('😀', 10, 4)]
+        );
+        assert_eq!(input.next(pos).unwrap_err().reasons[0].1, "Out of bounds");
+
+        let (s, end) = input.next_range(at(input, 1), 2).unwrap();
+        assert_eq!(s, "é日");
+        assert_eq!(end, at(input, 3));
+        assert!(input.next_range(at(input, 3), 2).is_err());
+    }
+
+    #[test]
+    fn bytes_reject_invalid_utf8() {
+        let reason = |input: &[u8]| input.next(Default::default()).unwrap_err().reasons[0].1;
+        assert_eq!(reason(b"\xff"), "Invalid UTF-8");
+        assert_eq!(reason(b"\x80"), "Invalid UTF-8");
+        assert_eq!(reason(b"\xe6\x97("), "Invalid UTF-8");
+        assert_eq!(reason(b"\xe6\x97"), "Out of bounds");
+
+        let input = &b"ab\xffc"[..];
+        let error = input.next_range(Default::default(), 3).unwrap_err();
+        assert_eq!(error.reasons[0], (at(input, 2), "Invalid UTF-8"));
+    }
+
+    #[test]
+    fn bytes_track_lines_after_crlf() {
+        let input = "a\r\n\u{e9}b".as_bytes();
+        let pos = at(input, 3);
+        assert_eq!((pos.offset, pos.line(), pos.column()), (3, 1, 0));
+        let pos = at(input, 5);
+        assert_eq!(
+            (pos.offset, pos.index(), pos.line(), pos.column()),
+            (6, 5, 1, 2)
+        );
+        assert_eq!(at(input, 5) - at(input, 1), 4);
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn context_is_send_and_sync() {
+        assert_send_sync::<ParserContext>();
+        assert_send_sync::<ParserOptions>();
+    }
+
+    crate::literals! {
+        AChar => 'a';
+        BChar => 'b';
+    }
+
+    #[test]
+    fn one_of_returns_the_deeper_failure() {
+        type AB = Concat<AChar, BChar>;
+        let input = &b"ac"[..];
+        let context = ParserContext::new(Default::default());
+        let error =
+            <OneOf<AB, BChar> as Parser<&[u8]>>::parse(&input, Default::default(), &context)
+                .map(|_| ())
+                .unwrap_err();
+        assert_eq!(error.reasons[0], (at(input, 1), "ExpectChar"));
+
+        let error =
+            <OneOf<BChar, AB> as Parser<&[u8]>>::parse(&input, Default::default(), &context)
+                .map(|_| ())
+                .unwrap_err();
+        assert_eq!(error.reasons[0], (at(input, 1), "ExpectChar"));
+    }
+}
@@ -0,0 +1,370 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec::Vec};
+
+use crate::json::{GenericJsonValue, NotFinite, NumberValue};
+use crate::object::{ObjectBackend, ObjectMap};
+use core::convert::TryFrom;
+use core::fmt;
+
+/// Error returned when a `JsonValue` cannot be converted into a Rust type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The value has another JSON type, such as a string where a number is expected.
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The number is not an integer or does not fit in the requested type.
+    OutOfRange { expected: &'static str },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::WrongType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ConversionError::OutOfRange { expected } => {
+                write!(f, "number out of range for {}", expected)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConversionError {}
+
+fn type_name<B: ObjectBackend>(value: &GenericJsonValue<B>) -> &'static str {
+    match value {
+        GenericJsonValue::Object(_) => "object",
+        GenericJsonValue::Array(_) => "array",
+        GenericJsonValue::String(_) => "string",
+        GenericJsonValue::Number(_) => "number",
+        GenericJsonValue::Boolean(_) => "boolean",
+        GenericJsonValue::Null => "null",
+    }
+}
+
+fn wrong_type<B: ObjectBackend>(
+    expected: &'static str,
+    value: &GenericJsonValue<B>,
+) -> ConversionError {
+    ConversionError::WrongType {
+        expected,
+        found: type_name(value),
+    }
+}
+
+/// Builds an integer `NumberValue`. Magnitudes beyond `u64` keep their exact text,
+/// with `integer` and `exponent` approximating them.
+fn integer_value(magnitude: u128, negative: bool) -> NumberValue {
+    if let Ok(integer) = u64::try_from(magnitude) {
+        return NumberValue::new(integer, 0, 0, 0, negative);
+    }
+    let (mut integer, mut exponent) = (magnitude, 0);
+    while integer > u64::MAX as u128 {
+        integer /= 10;
+        exponent += 1;
+    }
+    let sign = if negative { "-" } else { "" };
+    NumberValue::new(integer as u64, 0, 0, exponent, negative)
+        .with_lexical(format!("{}{}", sign, magnitude))
+}
+
+macro_rules! integer_conversions {
+    ($($ty:ident => $to:ident;)*) => {
+        $(
+            impl From<$ty> for NumberValue {
+                fn from(value: $ty) -> Self {
+                    #[allow(unused_comparisons)]
+                    let negative = value < 0;
+                    let magnitude = if negative {
+                        (value as i128).unsigned_abs()
+                    } else {
+                        value as u128
+                    };
+                    integer_value(magnitude, negative)
+                }
+            }
+
+            impl<B: ObjectBackend> From<$ty> for GenericJsonValue<B> {
+                fn from(value: $ty) -> Self {
+                    GenericJsonValue::Number(value.into())
+                }
+            }
+
+            impl<B: ObjectBackend> TryFrom<&GenericJsonValue<B>> for $ty {
+                type Error = ConversionError;
+
+                fn try_from(value: &GenericJsonValue<B>) -> Result<Self, Self::Error> {
+                    match value {
+                        GenericJsonValue::Number(n) => n.$to().ok_or(ConversionError::OutOfRange {
+                            expected: stringify!($ty),
+                        }),
+                        _ => Err(wrong_type(stringify!($ty), value)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer_conversions! {
+    u8 => to_u8;
+    u16 => to_u16;
+    u32 => to_u32;
+    u64 => to_u64;
+    u128 => to_u128;
+    usize => to_usize;
+    i8 => to_i8;
+    i16 => to_i16;
+    i32 => to_i32;
+    i64 => to_i64;
+    i128 => to_i128;
+    isize => to_isize;
+}
+
+/// Converts to the nearest `f64`, which may be infinite for very large numbers.
+impl<B: ObjectBackend> TryFrom<&GenericJsonValue<B>> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: &GenericJsonValue<B>) -> Result<Self, Self::Error> {
+        match value {
+            GenericJsonValue::Number(n) => Ok(n.to_f64()),
+            _ => Err(wrong_type("f64", value)),
+        }
+    }
+}
+
+impl<B: ObjectBackend> TryFrom<&GenericJsonValue<B>> for NumberValue {
+    type Error = ConversionError;
+
+    fn try_from(value: &GenericJsonValue<B>) -> Result<Self, Self::Error> {
+        match value {
+            GenericJsonValue::Number(n) => Ok(NumberValue::clone(n)),
+            _ => Err(wrong_type("number", value)),
+        }
+    }
+}
+
+/// Converts to the shortest number that round-trips. NaN and infinities are
+/// rejected, as JSON cannot represent them.
+impl<B: ObjectBackend> TryFrom<f64> for GenericJsonValue<B> {
+    type Error = NotFinite;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        NumberValue::try_from(value).map(GenericJsonValue::Number)
+    }
+}
+
+/// Converts to the shortest number that round-trips. NaN and infinities are
+/// rejected, as JSON cannot represent them.
+impl<B: ObjectBackend> TryFrom<f32> for GenericJsonValue<B> {
+    type Error = NotFinite;
+
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        NumberValue::try_from(value).map(GenericJsonValue::Number)
+    }
+}
+
+impl<B: ObjectBackend> From<NumberValue> for GenericJsonValue<B> {
+    fn from(value: NumberValue) -> Self {
+        GenericJsonValue::Number(value)
+    }
+}
+
+impl<B: ObjectBackend> From<bool> for GenericJsonValue<B> {
+    fn from(value: bool) -> Self {
+        GenericJsonValue::Boolean(value)
+    }
+}
+
+impl<B: ObjectBackend> TryFrom<&GenericJsonValue<B>> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: &GenericJsonValue<B>) -> Result<Self, Self::Error> {
+        match value {
+            GenericJsonValue::Boolean(b) => Ok(*b),
+            _ => Err(wrong_type("boolean", value)),
+        }
+    }
+}
+
+impl<B: ObjectBackend> From<&str> for GenericJsonValue<B> {
+    fn from(value: &str) -> Self {
+        GenericJsonValue::String(value.into())
+    }
+}
+
+impl<B: ObjectBackend> From<String> for GenericJsonValue<B> {
+    fn from(value: String) -> Self {
+        GenericJsonValue::String(value)
+    }
+}
+
+impl<B: ObjectBackend> TryFrom<&GenericJsonValue<B>> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: &GenericJsonValue<B>) -> Result<Self, Self::Error> {
+        match value {
+            GenericJsonValue::String(s) => Ok(s.clone()),
+            _ => Err(wrong_type("string", value)),
+        }
+    }
+}
+
+impl<B: ObjectBackend, T: Into<GenericJsonValue<B>>> From<Vec<T>> for GenericJsonValue<B> {
+    fn from(value: Vec<T>) -> Self {
+        GenericJsonValue::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<B: ObjectBackend, T> TryFrom<&GenericJsonValue<B>> for Vec<T>
+where
+    T: for<'a> TryFrom<&'a GenericJsonValue<B>, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: &GenericJsonValue<B>) -> Result<Self, Self::Error> {
+        match value {
+            GenericJsonValue::Array(values) => values.iter().map(T::try_from).collect(),
+            _ => Err(wrong_type("array", value)),
+        }
+    }
+}
+
+/// Converts `None` into `null`.
+impl<B: ObjectBackend, T: Into<GenericJsonValue<B>>> From<Option<T>> for GenericJsonValue<B> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(GenericJsonValue::Null, Into::into)
+    }
+}
+
+/// Collects key/value pairs into an object, keeping the first of duplicate keys
+/// like the parser.
+impl<B: ObjectBackend, K: AsRef<str>, V: Into<GenericJsonValue<B>>> core::iter::FromIterator<(K, V)>
+    for GenericJsonValue<B>
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        GenericJsonValue::Object(B::Object::from_members(
+            iter.into_iter()
+                .map(|(key, value)| (key.as_ref().into(), value.into()))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonValue;
+    use crate::json_parser::{parse_json, parse_json_with_options};
+    use crate::traits::Serialize;
+    use lite_parser::parser::{NumberOverflow, ParserOptions};
+
+    #[test]
+    fn it_builds_values_from_primitives() {
+        let value: JsonValue = vec![
+            ("small", JsonValue::from(-12i8)),
+            ("large", u64::MAX.into()),
+            ("min", i64::MIN.into()),
+            ("flag", true.into()),
+            ("name", "lite".into()),
+            ("owned", std::string::String::from("json").into()),
+            ("list", vec![1u16, 2, 3].into()),
+            ("nothing", None::<bool>.into()),
+            ("something", Some("x").into()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            value,
+            parse_json(r#"{"small":-12,"large":18446744073709551615,"min":-9223372036854775808,"flag":true,"name":"lite","owned":"json","list":[1,2,3],"nothing":null,"something":"x"}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_builds_numbers_from_wide_integers() {
+        let cases = [
+            (
+                JsonValue::from(u128::MAX),
+                "340282366920938463463374607431768211455",
+            ),
+            (
+                JsonValue::from(i128::MIN),
+                "-170141183460469231731687303715884105728",
+            ),
+            (JsonValue::from(-5i128), "-5"),
+            (
+                JsonValue::from(u64::MAX as u128 + 1),
+                "18446744073709551616",
+            ),
+        ];
+        for (value, text) in cases.iter() {
+            assert_eq!(value.serialize(), text.as_bytes());
+            let options = ParserOptions {
+                number_overflow: NumberOverflow::KeepText,
+                ..Default::default()
+            };
+            assert_eq!(*value, parse_json_with_options(text, options).unwrap());
+        }
+        assert_eq!(u128::try_from(&cases[0].0), Ok(u128::MAX));
+        assert_eq!(i128::try_from(&cases[1].0), Ok(i128::MIN));
+        assert_eq!(i64::try_from(&cases[2].0), Ok(-5));
+        assert_eq!(NumberValue::from(-5i128).lexical(), None);
+        assert_eq!(cases[0].0.as_number().unwrap().exponent(), 20);
+    }
+
+    #[test]
+    fn it_rejects_non_finite_floats() {
+        assert_eq!(JsonValue::try_from(2.5f64), Ok(parse_json("2.5").unwrap()));
+        assert_eq!(JsonValue::try_from(0.1f32), Ok(parse_json("0.1").unwrap()));
+        assert_eq!(JsonValue::try_from(f64::NAN), Err(NotFinite));
+        assert_eq!(JsonValue::try_from(f64::NEG_INFINITY), Err(NotFinite));
+        assert_eq!(JsonValue::try_from(f32::INFINITY), Err(NotFinite));
+    }
+
+    #[test]
+    fn it_extracts_primitives() {
+        let value = parse_json(r#"[300, -1.5e1, 2.50, "s", true, null, [1, 2]]"#).unwrap();
+        let values = value.as_array().unwrap();
+        assert_eq!(u16::try_from(&values[0]), Ok(300));
+        assert_eq!(i128::try_from(&values[1]), Ok(-15));
+        assert_eq!(f64::try_from(&values[2]), Ok(2.5));
+        assert_eq!(
+            std::string::String::try_from(&values[3]),
+            Ok("s".to_string())
+        );
+        assert_eq!(bool::try_from(&values[4]), Ok(true));
+        assert_eq!(Vec::<u8>::try_from(&values[6]), Ok(vec![1, 2]));
+
+        assert_eq!(
+            u8::try_from(&values[0]),
+            Err(ConversionError::OutOfRange { expected: "u8" })
+        );
+        assert_eq!(
+            u32::try_from(&values[1]),
+            Err(ConversionError::OutOfRange { expected: "u32" })
+        );
+        assert_eq!(
+            i64::try_from(&values[2]),
+            Err(ConversionError::OutOfRange { expected: "i64" })
+        );
+        assert_eq!(
+            bool::try_from(&values[5]),
+            Err(ConversionError::WrongType {
+                expected: "boolean",
+                found: "null"
+            })
+        );
+        let error = Vec::<bool>::try_from(&values[6]).unwrap_err();
+        assert_eq!(error.to_string(), "expected boolean, found number");
+        assert_eq!(
+            u8::try_from(&values[0]).unwrap_err().to_string(),
+            "number out of range for u8"
+        );
+    }
+}
@@ -5,139 +5,600 @@ extern crate alloc;
 use alloc::vec::Vec;
 
 #[cfg(not(feature = "std"))]
-use alloc::string::ToString;
+use alloc::{
+    format,
+    string::{String, ToString},
+};
 
-use crate::traits::Serialize;
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+
+#[cfg(feature = "object_btree")]
+use crate::object::BTreeObjects;
+#[cfg(feature = "object_indexmap")]
+use crate::object::IndexObjects;
+use crate::object::{ObjectBackend, ObjectMap, VecObjects};
+use crate::sink::{BufferOverflow, FmtSink};
+use crate::traits::{Serialize, Sink};
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::Index;
 
+/// A number as `(integer + fraction / 10^fraction_length) * 10^exponent`, negated
+/// if `negative`. `fraction_length` counts every fraction digit, including zeros, so
+/// `1.000` keeps its precision when serialized.
+///
+/// A parsed number may also keep the text it was written as, returned by `lexical`.
+/// The text is then what gets serialized and converted, and the parts only hold
+/// the nearest value they can represent. The parts are read through accessors and
+/// cannot be changed, so they never disagree with the text. Build numbers with
+/// `new` or `From`.
+///
+/// Equality is numeric, so `1.0`, `1` and `10e-1` are equal. Use `normalize` for a
+/// canonical representation.
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, PartialEq, Copy)]
+#[derive(Clone)]
 pub struct NumberValue {
-    pub integer: u64,
-    pub fraction: u64,
-    pub fraction_length: u32,
-    pub exponent: i32,
-    pub negative: bool,
+    integer: u64,
+    fraction: u64,
+    fraction_length: u32,
+    exponent: i32,
+    negative: bool,
+    lexical: Option<String>,
+}
+
+macro_rules! integer_accessors {
+    ($($(#[$attr:meta])* $name:ident => $ty:ty, $wide:ident;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&self) -> Option<$ty> {
+                self.$wide().and_then(|v| <$ty>::try_from(v).ok())
+            }
+        )*
+    };
 }
 
 impl NumberValue {
-    /// Losslessly convert the inner value to `f64`.
-    #[cfg(any(feature = "std", feature = "float"))]
-    pub fn to_f64(self) -> f64 {
-        self.into()
+    pub const fn new(
+        integer: u64,
+        fraction: u64,
+        fraction_length: u32,
+        exponent: i32,
+        negative: bool,
+    ) -> Self {
+        Self {
+            integer,
+            fraction,
+            fraction_length,
+            exponent,
+            negative,
+            lexical: None,
+        }
+    }
+
+    pub fn integer(&self) -> u64 {
+        self.integer
+    }
+
+    pub fn fraction(&self) -> u64 {
+        self.fraction
+    }
+
+    /// The number of fraction digits, including leading and trailing zeros.
+    pub fn fraction_length(&self) -> u32 {
+        self.fraction_length
+    }
+
+    pub fn exponent(&self) -> i32 {
+        self.exponent
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Builds a number from the text it was written as, such as `-1.50E+2`, with
+    /// the parts approximating it.
+    pub(crate) fn with_lexical(self, lexical: String) -> Self {
+        Self {
+            lexical: Some(lexical),
+            ..self
+        }
+    }
+
+    /// Returns the number exactly as written in the parsed document, if it was kept.
+    ///
+    /// Numbers with more fraction digits than `fraction` can hold keep their text, as
+    /// do overflowing integers and exponents with `NumberOverflow::KeepText`. With the
+    /// `arbitrary_precision` feature every parsed number does.
+    pub fn lexical(&self) -> Option<&str> {
+        self.lexical.as_deref()
+    }
+
+    /// Converts the value to the nearest `f64`, rounding correctly however many
+    /// digits it has. Values beyond the range of `f64` become infinite.
+    pub fn to_f64(&self) -> f64 {
+        // The number is written out and parsed by `core`, which implements
+        // correctly rounded decimal to binary conversion.
+        let mut buffer = [0u8; 64];
+        let remaining = {
+            let mut out = &mut buffer[..];
+            self.write_to(&mut out, &Default::default(), 0)
+                .map(|_| out.len())
+        };
+        match remaining {
+            Ok(remaining) => core::str::from_utf8(&buffer[..buffer.len() - remaining])
+                .unwrap()
+                .parse()
+                .unwrap(),
+            Err(BufferOverflow) => String::from_utf8(self.serialize())
+                .unwrap()
+                .parse()
+                .unwrap(),
+        }
     }
+
+    /// Returns the exact absolute value as `mantissa * 10^exponent`, with no
+    /// trailing zeros in `mantissa`, or None if the digits do not fit in `u128`.
+    fn significand(&self) -> Option<(u128, i64)> {
+        if let Some(lexical) = &self.lexical {
+            return lexical_significand(lexical);
+        }
+        let mut fraction = self.fraction;
+        let mut fraction_length = self.fraction_length;
+        while fraction != 0 && fraction % 10 == 0 {
+            fraction /= 10;
+            fraction_length = fraction_length.saturating_sub(1);
+        }
+        if fraction == 0 {
+            fraction_length = 0;
+        }
+        let mantissa = if self.integer == 0 {
+            fraction as u128
+        } else {
+            10u128
+                .checked_pow(fraction_length)
+                .and_then(|scale| (self.integer as u128).checked_mul(scale))
+                .and_then(|m| m.checked_add(fraction as u128))?
+        };
+        Some(strip_zeros(
+            mantissa,
+            self.exponent as i64 - fraction_length as i64,
+        ))
+    }
+
+    /// Returns the exact absolute value as `(mantissa, scale)`, meaning
+    /// `mantissa / 10^scale`, with no trailing zeros in `mantissa` unless `scale`
+    /// is zero.
+    fn decimal(&self) -> Option<(u128, u32)> {
+        let (mantissa, exponent) = self.significand()?;
+        if exponent >= 0 {
+            let scale = 10u128.checked_pow(u32::try_from(exponent).ok()?)?;
+            Some((mantissa.checked_mul(scale)?, 0))
+        } else {
+            Some((mantissa, u32::try_from(-exponent).ok()?))
+        }
+    }
+
+    /// Returns the exact absolute value if it is an integer that fits in `u128`.
+    fn magnitude(&self) -> Option<u128> {
+        match self.decimal()? {
+            (magnitude, 0) => Some(magnitude),
+            _ => None,
+        }
+    }
+
+    /// Returns the canonical representation of the value: trailing zeros are moved
+    /// into the exponent, the fraction is folded into `integer` when it fits, and
+    /// zero is positive. Numbers too precise to canonicalize are returned as is.
+    pub fn normalize(&self) -> Self {
+        let (mut mantissa, mut exponent) = match self.significand() {
+            Some(significand) => significand,
+            None => return self.clone(),
+        };
+        // Split off just enough low digits into the fraction for `integer` to fit.
+        let mut fraction = 0u128;
+        let mut fraction_length = 0u32;
+        while mantissa > u64::MAX as u128 {
+            fraction += (mantissa % 10) * 10u128.pow(fraction_length);
+            fraction_length += 1;
+            mantissa /= 10;
+        }
+        exponent += fraction_length as i64;
+        match (u64::try_from(fraction), i32::try_from(exponent)) {
+            (Ok(fraction), Ok(exponent)) => NumberValue::new(
+                mantissa as u64,
+                fraction,
+                fraction_length,
+                exponent,
+                self.negative && mantissa != 0,
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns the exact value as `(mantissa, scale)`, meaning `mantissa / 10^scale`,
+    /// if the mantissa fits in `i128`. This is the form taken by decimal types, such
+    /// as `rust_decimal::Decimal::from_i128_with_scale`.
+    pub fn to_decimal(&self) -> Option<(i128, u32)> {
+        let (mantissa, scale) = self.decimal()?;
+        let mantissa = if self.negative {
+            0i128.checked_sub_unsigned(mantissa)?
+        } else {
+            i128::try_from(mantissa).ok()?
+        };
+        Some((mantissa, scale))
+    }
+
+    /// Returns the value as `u128` if it is a non-negative integer in range,
+    /// computed exactly from the decimal digits.
+    pub fn to_u128(&self) -> Option<u128> {
+        match self.magnitude()? {
+            0 => Some(0),
+            _ if self.negative => None,
+            v => Some(v),
+        }
+    }
+
+    /// Returns the value as `i128` if it is an integer in range, computed exactly
+    /// from the decimal digits.
+    pub fn to_i128(&self) -> Option<i128> {
+        let magnitude = self.magnitude()?;
+        if self.negative {
+            0i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    integer_accessors! {
+        /// Returns the value as `u64` if it is a non-negative integer in range.
+        to_u64 => u64, to_u128;
+        /// Returns the value as `u32` if it is a non-negative integer in range.
+        to_u32 => u32, to_u128;
+        /// Returns the value as `u16` if it is a non-negative integer in range.
+        to_u16 => u16, to_u128;
+        /// Returns the value as `u8` if it is a non-negative integer in range.
+        to_u8 => u8, to_u128;
+        /// Returns the value as `usize` if it is a non-negative integer in range.
+        to_usize => usize, to_u128;
+        /// Returns the value as `i64` if it is an integer in range.
+        to_i64 => i64, to_i128;
+        /// Returns the value as `i32` if it is an integer in range.
+        to_i32 => i32, to_i128;
+        /// Returns the value as `i16` if it is an integer in range.
+        to_i16 => i16, to_i128;
+        /// Returns the value as `i8` if it is an integer in range.
+        to_i8 => i8, to_i128;
+        /// Returns the value as `isize` if it is an integer in range.
+        to_isize => isize, to_i128;
+    }
+}
+
+/// Moves the trailing zeros of `mantissa` into `exponent`.
+fn strip_zeros(mut mantissa: u128, mut exponent: i64) -> (u128, i64) {
+    if mantissa == 0 {
+        return (0, 0);
+    }
+    while mantissa % 10 == 0 {
+        mantissa /= 10;
+        exponent += 1;
+    }
+    (mantissa, exponent)
+}
+
+/// Computes `NumberValue::significand` from the number as written, such as `-12.50e+3`.
+fn lexical_significand(lexical: &str) -> Option<(u128, i64)> {
+    let unsigned = lexical.strip_prefix('-').unwrap_or(lexical);
+    let (digits, exponent) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], &unsigned[i + 1..]),
+        None => (unsigned, ""),
+    };
+    let (integer, fraction) = match digits.find('.') {
+        Some(i) => (&digits[..i], &digits[i + 1..]),
+        None => (digits, ""),
+    };
+    let mut exponent = {
+        let negative = exponent.starts_with('-');
+        let magnitude = exponent
+            .trim_start_matches(['+', '-'])
+            .bytes()
+            .fold(0i64, |acc, b| {
+                acc.saturating_mul(10).saturating_add((b - b'0') as i64)
+            });
+        if negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    };
+    // Move trailing zeros into the exponent so that they cannot overflow the mantissa.
+    let fraction = fraction.trim_end_matches('0');
+    let integer = if fraction.is_empty() {
+        let trimmed = integer.trim_end_matches('0');
+        exponent = exponent.saturating_add((integer.len() - trimmed.len()) as i64);
+        trimmed
+    } else {
+        integer
+    };
+    exponent = exponent.saturating_sub(fraction.len() as i64);
+    let mut mantissa = 0u128;
+    for b in integer.bytes().chain(fraction.bytes()) {
+        mantissa = mantissa.checked_mul(10)?.checked_add((b - b'0') as u128)?;
+    }
+    Some(strip_zeros(mantissa, exponent))
 }
 
-#[cfg(any(feature = "std", feature = "float"))]
-impl Into<f64> for NumberValue {
-    fn into(self) -> f64 {
-        #[cfg(not(feature = "std"))]
-        use num_traits::float::FloatCore as _;
+impl PartialEq for NumberValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.significand(), other.significand()) {
+            (Some(a), Some(b)) => a == b && (a.0 == 0 || self.negative == other.negative),
+            (Some(_), None) | (None, Some(_)) => false,
+            (None, None) => {
+                let (a, b) = (self.normalize(), other.normalize());
+                if a.lexical.is_some() && b.lexical.is_some() {
+                    return a.lexical == b.lexical;
+                }
+                (
+                    a.integer,
+                    a.fraction,
+                    a.fraction_length,
+                    a.exponent,
+                    a.negative,
+                ) == (
+                    b.integer,
+                    b.fraction,
+                    b.fraction_length,
+                    b.exponent,
+                    b.negative,
+                )
+            }
+        }
+    }
+}
 
-        let sign = if self.negative { -1.0 } else { 1.0 };
-        (self.integer as f64 + self.fraction as f64 / 10f64.powi(self.fraction_length as i32))
-            * 10f64.powi(self.exponent)
-            * sign
+impl From<NumberValue> for f64 {
+    fn from(val: NumberValue) -> Self {
+        val.to_f64()
     }
 }
 
-pub type JsonObject = Vec<(Vec<char>, JsonValue)>;
+/// Error returned when converting NaN or an infinity, which JSON cannot represent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotFinite;
 
-#[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, PartialEq)]
-pub enum JsonValue {
-    Object(JsonObject),
-    Array(Vec<JsonValue>),
-    String(Vec<char>),
+impl fmt::Display for NotFinite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("number is not finite")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NotFinite {}
+
+/// Builds a `NumberValue` from the shortest scientific notation of a float, such as
+/// `1.2345e-6` as written by `{:e}`, without an exponent where it is small.
+fn from_scientific(text: &str) -> NumberValue {
+    let negative = text.starts_with('-');
+    let text = text.trim_start_matches('-');
+    let (mantissa, exponent) = text.split_at(text.find('e').unwrap());
+    let exponent: i32 = exponent[1..].parse().unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let digit = |c: &str| c.parse::<u64>().unwrap_or(0);
+    let (integer, fraction, fraction_length, exponent) = if (0..19).contains(&exponent) {
+        // Move the decimal point right, padding the integer with zeros if needed.
+        let split = digits.len().min(exponent as usize + 1);
+        let integer = digit(&digits[..split]) * 10u64.pow(exponent as u32 + 1 - split as u32);
+        let fraction = &digits[split..];
+        (integer, digit(fraction), fraction.len() as u32, 0)
+    } else if (-6..0).contains(&exponent) {
+        let fraction_length = digits.len() as u32 + (-exponent) as u32 - 1;
+        (0, digit(&digits), fraction_length, 0)
+    } else {
+        let fraction = &digits[1..];
+        (
+            digit(&digits[..1]),
+            digit(fraction),
+            fraction.len() as u32,
+            exponent,
+        )
+    };
+    NumberValue::new(integer, fraction, fraction_length, exponent, negative)
+}
+
+/// Converts to the shortest decimal that parses back to the same `f64`.
+impl TryFrom<f64> for NumberValue {
+    type Error = NotFinite;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if !value.is_finite() {
+            return Err(NotFinite);
+        }
+        Ok(from_scientific(&format!("{:e}", value)))
+    }
+}
+
+/// Converts to the shortest decimal that parses back to the same `f32`.
+impl TryFrom<f32> for NumberValue {
+    type Error = NotFinite;
+
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        if !value.is_finite() {
+            return Err(NotFinite);
+        }
+        Ok(from_scientific(&format!("{:e}", value)))
+    }
+}
+
+pub use crate::object::JsonObject;
+
+/// A JSON value whose objects store their members as selected by `B`.
+///
+/// Use the aliases `JsonValue`, and `BTreeJsonValue` or `IndexJsonValue` with the
+/// `object_btree` or `object_indexmap` features.
+pub enum GenericJsonValue<B: ObjectBackend = VecObjects> {
+    Object(B::Object),
+    Array(Vec<GenericJsonValue<B>>),
+    String(String),
     Number(NumberValue),
     Boolean(bool),
     Null,
 }
 
-impl JsonValue {
-    /// Returns a boolean indicating whether this value is an object or not.
-    pub fn is_object(&self) -> bool {
+/// A JSON value whose objects keep their members in a `Vec` in document order.
+pub type JsonValue = GenericJsonValue<VecObjects>;
+
+/// A JSON value whose objects keep their members in a `BTreeMap` sorted by key.
+#[cfg(feature = "object_btree")]
+pub type BTreeJsonValue = GenericJsonValue<BTreeObjects>;
+
+/// A JSON value whose objects keep their members in an `IndexMap` in insertion order.
+#[cfg(feature = "object_indexmap")]
+pub type IndexJsonValue = GenericJsonValue<IndexObjects>;
+
+// Implemented by hand, as deriving would require `B::Object` to implement each
+// trait, which in turn requires it of the value.
+impl<B: ObjectBackend> Clone for GenericJsonValue<B> {
+    fn clone(&self) -> Self {
         match self {
-            JsonValue::Object(_) => true,
+            GenericJsonValue::Object(obj) => GenericJsonValue::Object(obj.clone()),
+            GenericJsonValue::Array(arr) => GenericJsonValue::Array(arr.clone()),
+            GenericJsonValue::String(s) => GenericJsonValue::String(s.clone()),
+            GenericJsonValue::Number(n) => GenericJsonValue::Number(n.clone()),
+            GenericJsonValue::Boolean(b) => GenericJsonValue::Boolean(*b),
+            GenericJsonValue::Null => GenericJsonValue::Null,
+        }
+    }
+}
+
+impl<B: ObjectBackend> PartialEq for GenericJsonValue<B> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (GenericJsonValue::Object(a), GenericJsonValue::Object(b)) => a == b,
+            (GenericJsonValue::Array(a), GenericJsonValue::Array(b)) => a == b,
+            (GenericJsonValue::String(a), GenericJsonValue::String(b)) => a == b,
+            (GenericJsonValue::Number(a), GenericJsonValue::Number(b)) => a == b,
+            (GenericJsonValue::Boolean(a), GenericJsonValue::Boolean(b)) => a == b,
+            (GenericJsonValue::Null, GenericJsonValue::Null) => true,
             _ => false,
         }
     }
+}
 
-    /// Returns a reference to the key-value vec if this value is an object, otherwise returns None.
-    pub fn as_object(&self) -> Option<&[(Vec<char>, JsonValue)]> {
+#[cfg(feature = "std")]
+impl<B: ObjectBackend> fmt::Debug for GenericJsonValue<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            JsonValue::Object(obj) => Some(obj),
+            GenericJsonValue::Object(obj) => f.debug_tuple("Object").field(obj).finish(),
+            GenericJsonValue::Array(arr) => f.debug_tuple("Array").field(arr).finish(),
+            GenericJsonValue::String(s) => f.debug_tuple("String").field(s).finish(),
+            GenericJsonValue::Number(n) => f.debug_tuple("Number").field(n).finish(),
+            GenericJsonValue::Boolean(b) => f.debug_tuple("Boolean").field(b).finish(),
+            GenericJsonValue::Null => f.write_str("Null"),
+        }
+    }
+}
+
+impl<B: ObjectBackend> GenericJsonValue<B> {
+    /// Returns a boolean indicating whether this value is an object or not.
+    pub fn is_object(&self) -> bool {
+        matches!(self, GenericJsonValue::Object(_))
+    }
+
+    /// Returns a reference to the members if this value is an object, otherwise returns None.
+    pub fn as_object(&self) -> Option<&B::Object> {
+        match self {
+            GenericJsonValue::Object(obj) => Some(obj),
             _ => None,
         }
     }
 
     /// Returns the wrapped object if the value is an object, otherwise returns None.
-    pub fn to_object(self) -> Option<JsonObject> {
+    pub fn to_object(self) -> Option<B::Object> {
         match self {
-            JsonValue::Object(obj) => Some(obj),
+            GenericJsonValue::Object(obj) => Some(obj),
             _ => None,
         }
     }
 
     /// Returns a boolean indicating whether this value is an array or not.
     pub fn is_array(&self) -> bool {
-        match self {
-            JsonValue::Array(_) => true,
-            _ => false,
-        }
+        matches!(self, GenericJsonValue::Array(_))
     }
 
     /// Returns a reference to the wrapped array if this value is an array, otherwise returns None.
-    pub fn as_array(&self) -> Option<&[JsonValue]> {
+    pub fn as_array(&self) -> Option<&[Self]> {
         match self {
-            JsonValue::Array(arr) => Some(arr),
+            GenericJsonValue::Array(arr) => Some(arr),
             _ => None,
         }
     }
 
     /// Returns the wrapped vector if the value is an array, otherwise returns None.
-    pub fn to_array(self) -> Option<Vec<JsonValue>> {
+    pub fn to_array(self) -> Option<Vec<Self>> {
         match self {
-            JsonValue::Array(arr) => Some(arr),
+            GenericJsonValue::Array(arr) => Some(arr),
             _ => None,
         }
     }
 
     /// Returns a boolean indicating whether this value is a string or not.
     pub fn is_string(&self) -> bool {
-        match self {
-            JsonValue::String(_) => true,
-            _ => false,
-        }
+        matches!(self, GenericJsonValue::String(_))
     }
 
-    /// Returns a char slice if this value is a string, otherwise returns None.
-    pub fn as_string(&self) -> Option<&[char]> {
+    /// Returns the string slice if this value is a string, otherwise returns None.
+    pub fn as_str(&self) -> Option<&str> {
         match self {
-            JsonValue::String(s) => Some(s),
+            GenericJsonValue::String(s) => Some(s),
             _ => None,
         }
     }
 
-    /// Returns the wrapped vector if the value is a string, otherwise returns None.
-    pub fn to_string(self) -> Option<Vec<char>> {
+    /// Returns the string slice if this value is a string, otherwise returns None.
+    ///
+    /// This used to return `&[char]`. Strings are now stored as `String`, so there is
+    /// no char slice to borrow.
+    #[deprecated(note = "use `as_str`, or `to_chars` for a copy as `Vec<char>`")]
+    pub fn as_string(&self) -> Option<&str> {
+        self.as_str()
+    }
+
+    /// Returns a copy of the string as chars if this value is a string, otherwise
+    /// returns None.
+    #[deprecated(note = "strings are stored as `String`, use `as_str` instead")]
+    pub fn to_chars(&self) -> Option<Vec<char>> {
+        self.as_str().map(|s| s.chars().collect())
+    }
+
+    /// Returns the wrapped string if the value is a string, otherwise returns None.
+    pub fn into_string(self) -> Option<String> {
         match self {
-            JsonValue::String(s) => Some(s),
+            GenericJsonValue::String(s) => Some(s),
             _ => None,
         }
     }
 
+    /// Returns the wrapped string as chars if the value is a string, otherwise
+    /// returns None.
+    #[deprecated(
+        note = "strings are stored as `String`, use `into_string` instead, or `ToString::to_string` for the JSON text"
+    )]
+    pub fn to_string(self) -> Option<Vec<char>> {
+        self.into_string().map(|s| s.chars().collect())
+    }
+
     /// Returns a boolean indicating whether this value is a number or not.
     pub fn is_number(&self) -> bool {
-        match self {
-            JsonValue::Number(_) => true,
-            _ => false,
-        }
+        matches!(self, GenericJsonValue::Number(_))
     }
 
     /// Returns a reference to wrapped `NumberValue` if this value is a number, otherwise returns None.
     pub fn as_number(&self) -> Option<&NumberValue> {
         match self {
-            JsonValue::Number(n) => Some(n),
+            GenericJsonValue::Number(n) => Some(n),
             _ => None,
         }
     }
@@ -145,23 +606,20 @@ impl JsonValue {
     /// Returns the wrapped NumberValue if the value is a number, otherwise returns None.
     pub fn to_number(self) -> Option<NumberValue> {
         match self {
-            JsonValue::Number(n) => Some(n),
+            GenericJsonValue::Number(n) => Some(n),
             _ => None,
         }
     }
 
     /// Returns a boolean indicating whether this value is a boolean or not.
     pub fn is_bool(&self) -> bool {
-        match self {
-            JsonValue::Boolean(_) => true,
-            _ => false,
-        }
+        matches!(self, GenericJsonValue::Boolean(_))
     }
 
     /// Returns a reference to the wrapped boolean if this value is a boolean, otherwise returns None.
     pub fn as_bool(&self) -> Option<&bool> {
         match self {
-            JsonValue::Boolean(b) => Some(b),
+            GenericJsonValue::Boolean(b) => Some(b),
             _ => None,
         }
     }
@@ -169,148 +627,560 @@ impl JsonValue {
     /// Returns the wrapped boolean if the value is a boolean, otherwise returns None.
     pub fn to_bool(self) -> Option<bool> {
         match self {
-            JsonValue::Boolean(b) => Some(b),
+            GenericJsonValue::Boolean(b) => Some(b),
             _ => None,
         }
     }
 
     /// Returns a boolean indicating whether this value is null or not.
     pub fn is_null(&self) -> bool {
+        matches!(self, GenericJsonValue::Null)
+    }
+
+    /// Returns a reference to the value of the first member named `key` if this value
+    /// is an object, otherwise returns None.
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        self.as_object()?.member(key)
+    }
+
+    /// Returns a mutable reference to the value of the first member named `key` if this
+    /// value is an object, otherwise returns None.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Self> {
         match self {
-            JsonValue::Null => true,
-            _ => false,
+            GenericJsonValue::Object(obj) => obj.member_mut(key),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the element at `index` if this value is an array,
+    /// otherwise returns None.
+    pub fn get_index(&self, index: usize) -> Option<&Self> {
+        self.as_array()?.get(index)
+    }
+
+    /// Returns a boolean indicating whether this value is an object with a member named `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns an iterator over the member names if this value is an object, otherwise
+    /// an empty iterator.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.as_object()
+            .into_iter()
+            .flat_map(|obj| obj.members().map(|(k, _)| k))
+    }
+
+    /// Returns an iterator over the member values if this value is an object, otherwise
+    /// an empty iterator.
+    pub fn values(&self) -> impl Iterator<Item = &Self> {
+        self.as_object()
+            .into_iter()
+            .flat_map(|obj| obj.members().map(|(_, v)| v))
+    }
+
+    /// Returns the number of members of an object or elements of an array, or zero
+    /// for other values.
+    pub fn len(&self) -> usize {
+        match self {
+            GenericJsonValue::Object(obj) => obj.len(),
+            GenericJsonValue::Array(arr) => arr.len(),
+            _ => 0,
+        }
+    }
+
+    /// Returns a boolean indicating whether `len` is zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the members of this object, turning `null` into an empty object first.
+    fn object_mut(&mut self) -> &mut B::Object {
+        if self.is_null() {
+            *self = GenericJsonValue::Object(B::Object::default());
+        }
+        match self {
+            GenericJsonValue::Object(obj) => obj,
+            _ => panic!("JsonValue is not an object"),
+        }
+    }
+
+    /// Sets the member named `key`, returning its previous value.
+    ///
+    /// Objects may keep duplicate keys in document order, and lookups see the first
+    /// one. An existing member is replaced in place and any later members with the
+    /// same key are removed, so the object is left with exactly one `key`. A new
+    /// member is added at the end.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this value is neither an object nor `null`, which becomes an object.
+    pub fn insert(&mut self, key: &str, value: impl Into<Self>) -> Option<Self> {
+        self.object_mut().set_member(key, value.into())
+    }
+
+    /// Removes every member named `key`, returning the value of the first, if this
+    /// value is an object.
+    pub fn remove(&mut self, key: &str) -> Option<Self> {
+        match self {
+            GenericJsonValue::Object(obj) => obj.remove_member(key),
+            _ => None,
+        }
+    }
+
+    /// Keeps only the members for which `f` returns true, if this value is an object.
+    pub fn retain(&mut self, f: impl FnMut(&str, &mut Self) -> bool) {
+        if let GenericJsonValue::Object(obj) = self {
+            obj.retain_members(f);
+        }
+    }
+
+    /// Returns the entry of the first member named `key` for in-place manipulation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this value is neither an object nor `null`, which becomes an object.
+    pub fn entry<'a>(&'a mut self, key: &'a str) -> Entry<'a, B> {
+        Entry {
+            object: self.object_mut(),
+            key,
+        }
+    }
+
+    /// Appends an element to this array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this value is neither an array nor `null`, which becomes an array.
+    pub fn push(&mut self, value: impl Into<Self>) {
+        if self.is_null() {
+            *self = GenericJsonValue::Array(Vec::new());
+        }
+        match self {
+            GenericJsonValue::Array(arr) => arr.push(value.into()),
+            _ => panic!("JsonValue is not an array"),
+        }
+    }
+
+    /// Takes the value out, leaving `null` in its place.
+    pub fn take(&mut self) -> Self {
+        core::mem::replace(self, GenericJsonValue::Null)
+    }
+}
+
+/// A member of an object that may or may not exist, returned by `JsonValue::entry`.
+pub struct Entry<'a, B: ObjectBackend = VecObjects> {
+    object: &'a mut B::Object,
+    key: &'a str,
+}
+
+impl<'a, B: ObjectBackend> Entry<'a, B> {
+    /// Returns the value of the member, inserting `default` if it does not exist.
+    pub fn or_insert(self, default: impl Into<GenericJsonValue<B>>) -> &'a mut GenericJsonValue<B> {
+        self.or_insert_with(|| default.into())
+    }
+
+    /// Returns the value of the member, inserting the result of `f` if it does not exist.
+    pub fn or_insert_with(
+        self,
+        f: impl FnOnce() -> GenericJsonValue<B>,
+    ) -> &'a mut GenericJsonValue<B> {
+        self.object.member_or_insert_with(self.key, f)
+    }
+
+    /// Calls `f` with the value of the member if it exists.
+    pub fn and_modify(self, f: impl FnOnce(&mut GenericJsonValue<B>)) -> Self {
+        if let Some(value) = self.object.member_mut(self.key) {
+            f(value);
+        }
+        self
+    }
+}
+
+/// Returns the value of the member named `key`, or `null` if there is none or this
+/// value is not an object.
+impl<B: ObjectBackend> Index<&str> for GenericJsonValue<B> {
+    type Output = Self;
+
+    fn index(&self, key: &str) -> &GenericJsonValue<B> {
+        self.get(key).unwrap_or(&GenericJsonValue::Null)
+    }
+}
+
+/// Returns the element at `index`, or `null` if it is out of bounds or this value is
+/// not an array.
+impl<B: ObjectBackend> Index<usize> for GenericJsonValue<B> {
+    type Output = Self;
+
+    fn index(&self, index: usize) -> &GenericJsonValue<B> {
+        self.get_index(index).unwrap_or(&GenericJsonValue::Null)
+    }
+}
+
+pub type JsonObjectRef<'a> = Vec<(Cow<'a, str>, JsonValueRef<'a>)>;
+
+/// A JSON value whose strings and object keys borrow from the parsed input.
+///
+/// Strings are only allocated when they contain escape sequences that need decoding.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq)]
+pub enum JsonValueRef<'a> {
+    Object(JsonObjectRef<'a>),
+    Array(Vec<JsonValueRef<'a>>),
+    String(Cow<'a, str>),
+    Number(NumberValue),
+    Boolean(bool),
+    Null,
+}
+
+impl<'a> JsonValueRef<'a> {
+    /// Returns the string slice if this value is a string, otherwise returns None.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValueRef::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Converts into an owned `JsonValue`, copying all borrowed strings.
+    pub fn into_owned(self) -> JsonValue {
+        self.into()
+    }
+}
+
+impl<'a, B: ObjectBackend> From<JsonValueRef<'a>> for GenericJsonValue<B> {
+    fn from(value: JsonValueRef<'a>) -> Self {
+        match value {
+            JsonValueRef::Object(obj) => GenericJsonValue::Object(B::Object::from_members(
+                obj.into_iter()
+                    .map(|(key, val)| (key.into(), val.into()))
+                    .collect(),
+            )),
+            JsonValueRef::Array(arr) => {
+                GenericJsonValue::Array(arr.into_iter().map(Into::into).collect())
+            }
+            JsonValueRef::String(s) => GenericJsonValue::String(s.into_owned()),
+            JsonValueRef::Number(n) => GenericJsonValue::Number(n),
+            JsonValueRef::Boolean(b) => GenericJsonValue::Boolean(b),
+            JsonValueRef::Null => GenericJsonValue::Null,
+        }
+    }
+}
+
+/// Line terminator written by the pretty printer.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+/// Output configuration for `Serialize`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Spaces, or tabs with `use_tabs`, per nesting level. Zero writes the whole
+    /// document on one line.
+    pub indent: u32,
+    pub use_tabs: bool,
+    /// Write `"key": value` instead of `"key":value`.
+    pub space_after_colon: bool,
+    /// End the document with a line ending.
+    pub trailing_newline: bool,
+    /// When indenting, write arrays and objects on a single line if that line,
+    /// including its indentation, is at most this many characters long.
+    pub max_width: Option<u32>,
+    pub line_ending: LineEnding,
+    /// Escape every non-ASCII character as `\uXXXX`, using a surrogate pair
+    /// for characters outside the Basic Multilingual Plane.
+    pub ascii_only: bool,
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029 so the output can be embedded
+    /// in an HTML `<script>` element or JavaScript source.
+    pub html_safe: bool,
+}
+
+impl FormatOptions {
+    /// Indents by `indent` spaces per level with a space after each colon, as
+    /// used by `Serialize::format`.
+    pub fn pretty(indent: u32) -> Self {
+        Self {
+            indent,
+            space_after_colon: indent > 0,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn new_line(&self) -> &'static str {
+        match self.line_ending {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
         }
     }
 }
 
 impl Serialize for NumberValue {
-    fn serialize_to(&self, buffer: &mut Vec<u8>, _indent: u32, _level: u32) {
+    fn serialize_to(&self, buffer: &mut Vec<u8>, indent: u32, level: u32) {
+        self.serialize_to_with(buffer, &FormatOptions::pretty(indent), level);
+    }
+    fn serialize_to_with(&self, buffer: &mut Vec<u8>, options: &FormatOptions, level: u32) {
+        match self.write_to(buffer, options, level) {
+            Ok(()) => {}
+            Err(never) => match never {},
+        }
+    }
+    fn write_to<S: Sink>(
+        &self,
+        sink: &mut S,
+        _options: &FormatOptions,
+        _level: u32,
+    ) -> Result<(), S::Error> {
+        if let Some(lexical) = &self.lexical {
+            return sink.write_str(lexical);
+        }
         if self.negative {
-            buffer.push(b'-');
+            sink.write_str("-")?;
         }
-        buffer.extend_from_slice(self.integer.to_string().as_bytes());
+        sink.write_str(&self.integer.to_string())?;
 
-        if self.fraction > 0 {
-            buffer.push(b'.');
+        if self.fraction_length > 0 {
+            sink.write_str(".")?;
 
             let fraction_nums = self.fraction.to_string();
             let fraction_length = self.fraction_length as usize;
-            for _ in 0..fraction_length - fraction_nums.len() {
-                buffer.push(b'0');
+            for _ in 0..fraction_length.saturating_sub(fraction_nums.len()) {
+                sink.write_str("0")?;
             }
-            buffer.extend_from_slice(fraction_nums.as_bytes())
+            sink.write_str(&fraction_nums)?;
         }
         if self.exponent != 0 {
-            buffer.push(b'e');
+            sink.write_str("e")?;
             if self.exponent < 0 {
-                buffer.push(b'-');
+                sink.write_str("-")?;
             }
-            buffer.extend_from_slice(self.exponent.abs().to_string().as_bytes());
-        }
-    }
-}
-
-fn push_string(buffer: &mut Vec<u8>, chars: &Vec<char>) {
-    buffer.push('"' as u8);
-    for ch in chars {
-        match ch {
-            '\x08' => buffer.extend_from_slice(br#"\b"#),
-            '\x0c' => buffer.extend_from_slice(br#"\f"#),
-            '\n' => buffer.extend_from_slice(br#"\n"#),
-            '\r' => buffer.extend_from_slice(br#"\r"#),
-            '\t' => buffer.extend_from_slice(br#"\t"#),
-            '\"' => buffer.extend_from_slice(br#"\""#),
-            '\\' => buffer.extend_from_slice(br#"\\"#),
-            _ => match ch.len_utf8() {
-                1 => {
-                    let mut buff = [0u8; 1];
-                    ch.encode_utf8(&mut buff);
-                    buffer.push(buff[0]);
-                }
-                2 => {
-                    let mut buff = [0u8; 2];
-                    ch.encode_utf8(&mut buff);
-                    buffer.extend_from_slice(&buff);
-                }
-                3 => {
-                    let mut buff = [0u8; 3];
-                    ch.encode_utf8(&mut buff);
-                    buffer.extend_from_slice(&buff);
-                }
-                4 => {
-                    let mut buff = [0u8; 4];
-                    ch.encode_utf8(&mut buff);
-                    buffer.extend_from_slice(&buff);
+            sink.write_str(&self.exponent.unsigned_abs().to_string())?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn write_unicode_escape<S: Sink>(sink: &mut S, code: u16) -> Result<(), S::Error> {
+    const HEX: &str = "0123456789abcdef";
+    sink.write_str("\\u")?;
+    for shift in [12, 8, 4, 0].iter() {
+        let digit = (code >> shift) as usize & 0xf;
+        sink.write_str(&HEX[digit..digit + 1])?;
+    }
+    Ok(())
+}
+
+/// Writes `value` as a JSON string. Runs of characters that need no escaping are
+/// written to the sink as whole slices.
+pub(crate) fn write_string<S: Sink>(
+    sink: &mut S,
+    value: &str,
+    options: &FormatOptions,
+) -> Result<(), S::Error> {
+    sink.write_str("\"")?;
+    write_string_contents(sink, value, options)?;
+    sink.write_str("\"")
+}
+
+/// Writes `value` as the contents of a JSON string, without the quotes.
+pub(crate) fn write_string_contents<S: Sink>(
+    sink: &mut S,
+    value: &str,
+    options: &FormatOptions,
+) -> Result<(), S::Error> {
+    let mut start = 0;
+    for (index, ch) in value.char_indices() {
+        // The short escape of `ch`, or `None` to write it as `\u` escapes.
+        let escape = match ch {
+            '\x08' => Some("\\b"),
+            '\x0c' => Some("\\f"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            '\"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\x00'..='\x1f' => None,
+            '<' | '>' | '&' | '\u{2028}' | '\u{2029}' if options.html_safe => None,
+            _ if options.ascii_only && !ch.is_ascii() => None,
+            _ => continue,
+        };
+        sink.write_str(&value[start..index])?;
+        start = index + ch.len_utf8();
+        match escape {
+            Some(escape) => sink.write_str(escape)?,
+            None => {
+                let mut units = [0u16; 2];
+                for unit in ch.encode_utf16(&mut units) {
+                    write_unicode_escape(sink, *unit)?;
                 }
-                _ => panic!("Invalid UTF8 character"),
-            },
+            }
         }
     }
-    buffer.push('"' as u8);
+    sink.write_str(&value[start..])
 }
 
-fn push_new_line_indent(buffer: &mut Vec<u8>, indent: u32, level: u32) {
-    if indent > 0 {
-        buffer.push('\n' as u8);
+pub(crate) fn write_new_line_indent<S: Sink>(
+    sink: &mut S,
+    options: &FormatOptions,
+    level: u32,
+) -> Result<(), S::Error> {
+    const SPACES: &str = "                                ";
+    const TABS: &str = "\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t";
+    let fill = if options.use_tabs { TABS } else { SPACES };
+    sink.write_str(options.new_line())?;
+    let mut count = (options.indent * level) as usize;
+    while count > 0 {
+        let n = count.min(fill.len());
+        sink.write_str(&fill[..n])?;
+        count -= n;
     }
-    let count = (indent * level) as usize;
-    buffer.reserve(count);
-    for _ in 0..count {
-        buffer.push(' ' as u8);
+    Ok(())
+}
+
+/// Counts written characters, failing once more than `remaining` are written.
+struct Measure {
+    remaining: usize,
+}
+
+impl Sink for Measure {
+    type Error = ();
+
+    fn write_str(&mut self, s: &str) -> Result<(), ()> {
+        self.remaining = self.remaining.checked_sub(s.chars().count()).ok_or(())?;
+        Ok(())
     }
 }
 
-impl Serialize for JsonValue {
-    fn serialize_to(&self, buffer: &mut Vec<u8>, indent: u32, level: u32) {
-        match self {
-            JsonValue::Object(obj) => {
-                buffer.push('{' as u8);
-                if obj.len() > 0 {
-                    push_new_line_indent(buffer, indent, level + 1);
-                    push_string(buffer, &obj[0].0);
-                    buffer.push(':' as u8);
-                    if indent > 0 {
-                        buffer.push(' ' as u8);
-                    }
-                    obj[0].1.serialize_to(buffer, indent, level + 1);
-                    for (key, val) in obj.iter().skip(1) {
-                        buffer.push(',' as u8);
-                        push_new_line_indent(buffer, indent, level + 1);
-                        push_string(buffer, key);
-                        buffer.push(':' as u8);
-                        if indent > 0 {
-                            buffer.push(' ' as u8);
-                        }
-                        val.serialize_to(buffer, indent, level + 1);
-                    }
-                    push_new_line_indent(buffer, indent, level);
-                    buffer.push('}' as u8);
-                } else {
-                    buffer.push('}' as u8);
+/// Returns whether `value` fits on the current line when written inline, after
+/// `prefix` characters and before a possible trailing comma.
+fn fits_on_line<B: ObjectBackend>(
+    value: &GenericJsonValue<B>,
+    options: &FormatOptions,
+    level: u32,
+    prefix: usize,
+) -> bool {
+    let max_width = match options.max_width {
+        Some(max_width) => max_width as usize,
+        None => return false,
+    };
+    let used = (options.indent * level) as usize + prefix + 1;
+    match max_width.checked_sub(used) {
+        Some(remaining) => {
+            write_value(&mut Measure { remaining }, value, options, level, 0, true).is_ok()
+        }
+        None => false,
+    }
+}
+
+/// Writes `value` at nesting `level`, `prefix` characters into the line. Values
+/// written `inline` never break across lines.
+fn write_value<S: Sink, B: ObjectBackend>(
+    sink: &mut S,
+    value: &GenericJsonValue<B>,
+    options: &FormatOptions,
+    level: u32,
+    prefix: usize,
+    inline: bool,
+) -> Result<(), S::Error> {
+    let pretty = options.indent > 0;
+    let multiline = match value {
+        GenericJsonValue::Object(obj) if !obj.is_empty() => true,
+        GenericJsonValue::Array(arr) if !arr.is_empty() => true,
+        _ => false,
+    } && pretty
+        && !inline
+        && !fits_on_line(value, options, level, prefix);
+    let separator = if pretty && !multiline { ", " } else { "," };
+    match value {
+        GenericJsonValue::Object(obj) => {
+            sink.write_str("{")?;
+            for (i, (key, val)) in obj.members().enumerate() {
+                if i > 0 {
+                    sink.write_str(separator)?;
+                }
+                if multiline {
+                    write_new_line_indent(sink, options, level + 1)?;
                 }
+                write_string(sink, key, options)?;
+                sink.write_str(":")?;
+                let mut key_width = 0;
+                if multiline && options.max_width.is_some() {
+                    let mut measure = Measure {
+                        remaining: usize::MAX,
+                    };
+                    let _ = write_string(&mut measure, key, options);
+                    key_width = usize::MAX - measure.remaining + 1;
+                }
+                if options.space_after_colon {
+                    sink.write_str(" ")?;
+                    key_width += 1;
+                }
+                write_value(sink, val, options, level + 1, key_width, !multiline)?;
             }
-            JsonValue::Array(arr) => {
-                buffer.push('[' as u8);
-                if arr.len() > 0 {
-                    push_new_line_indent(buffer, indent, level + 1);
-                    arr[0].serialize_to(buffer, indent, level + 1);
-                    for val in arr.iter().skip(1) {
-                        buffer.push(',' as u8);
-                        push_new_line_indent(buffer, indent, level + 1);
-                        val.serialize_to(buffer, indent, level);
-                    }
-                    push_new_line_indent(buffer, indent, level);
-                    buffer.push(']' as u8);
-                } else {
-                    buffer.push(']' as u8);
+            if multiline {
+                write_new_line_indent(sink, options, level)?;
+            }
+            sink.write_str("}")
+        }
+        GenericJsonValue::Array(arr) => {
+            sink.write_str("[")?;
+            for (i, val) in arr.iter().enumerate() {
+                if i > 0 {
+                    sink.write_str(separator)?;
                 }
+                if multiline {
+                    write_new_line_indent(sink, options, level + 1)?;
+                }
+                write_value(sink, val, options, level + 1, 0, !multiline)?;
+            }
+            if multiline {
+                write_new_line_indent(sink, options, level)?;
             }
-            JsonValue::String(str) => push_string(buffer, str),
-            JsonValue::Number(num) => num.serialize_to(buffer, indent, level),
-            JsonValue::Boolean(true) => buffer.extend_from_slice(b"true"),
-            JsonValue::Boolean(false) => buffer.extend_from_slice(b"false"),
-            JsonValue::Null => buffer.extend_from_slice(b"null"),
+            sink.write_str("]")
         }
+        GenericJsonValue::String(str) => write_string(sink, str, options),
+        GenericJsonValue::Number(num) => num.write_to(sink, options, level),
+        GenericJsonValue::Boolean(true) => sink.write_str("true"),
+        GenericJsonValue::Boolean(false) => sink.write_str("false"),
+        GenericJsonValue::Null => sink.write_str("null"),
+    }
+}
+
+impl<B: ObjectBackend> Serialize for GenericJsonValue<B> {
+    fn serialize_to(&self, buffer: &mut Vec<u8>, indent: u32, level: u32) {
+        self.serialize_to_with(buffer, &FormatOptions::pretty(indent), level);
+    }
+    fn serialize_to_with(&self, buffer: &mut Vec<u8>, options: &FormatOptions, level: u32) {
+        match self.write_to(buffer, options, level) {
+            Ok(()) => {}
+            Err(never) => match never {},
+        }
+    }
+    fn write_to<S: Sink>(
+        &self,
+        sink: &mut S,
+        options: &FormatOptions,
+        level: u32,
+    ) -> Result<(), S::Error> {
+        write_value(sink, self, options, level, 0, false)
+    }
+}
+
+impl fmt::Display for NumberValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(&mut FmtSink(f), &Default::default(), 0)
+    }
+}
+
+/// Writes the value as compact JSON, or indented by four spaces with `{:#}`.
+impl<B: ObjectBackend> fmt::Display for GenericJsonValue<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = FormatOptions::pretty(if f.alternate() { 4 } else { 0 });
+        self.write_to(&mut FmtSink(f), &options, 0)
     }
 }
 
@@ -320,21 +1190,27 @@ mod tests {
 
     #[test]
     fn json_value_convenience_methods() {
-        let obj = JsonValue::Object(vec![(vec![], JsonValue::Null)]);
+        let obj = JsonValue::Object(JsonObject::from_members(vec![("".into(), JsonValue::Null)]));
         assert!(obj.is_object());
-        assert_eq!(obj.as_object(), Some(&[(vec![], JsonValue::Null)][..]));
+        assert_eq!(
+            obj.as_object(),
+            Some(&JsonObject::from_members(vec![(
+                "".into(),
+                JsonValue::Null
+            )]))
+        );
         assert_eq!(obj.as_array(), None);
         assert_eq!(obj.as_bool(), None);
         assert_eq!(obj.as_number(), None);
-        assert_eq!(obj.as_string(), None);
+        assert_eq!(obj.as_str(), None);
         assert_eq!(
             obj.clone().to_object(),
-            Some(vec![(vec![], JsonValue::Null)]),
+            Some(JsonObject::from_members(vec![("".into(), JsonValue::Null)])),
         );
         assert_eq!(obj.clone().to_array(), None);
         assert_eq!(obj.clone().to_bool(), None);
         assert_eq!(obj.clone().to_number(), None);
-        assert_eq!(obj.clone().to_string(), None);
+        assert_eq!(obj.clone().into_string(), None);
 
         let arr = JsonValue::Array(vec![JsonValue::Null]);
         assert!(arr.is_array());
@@ -342,61 +1218,48 @@ mod tests {
         assert_eq!(arr.as_object(), None);
         assert_eq!(arr.as_bool(), None);
         assert_eq!(arr.as_number(), None);
-        assert_eq!(arr.as_string(), None);
+        assert_eq!(arr.as_str(), None);
         assert_eq!(arr.clone().to_array(), Some(vec![JsonValue::Null]));
         assert_eq!(arr.clone().to_object(), None);
         assert_eq!(arr.clone().to_bool(), None);
         assert_eq!(arr.clone().to_number(), None);
-        assert_eq!(arr.clone().to_string(), None);
+        assert_eq!(arr.clone().into_string(), None);
 
-        let s = JsonValue::String(vec!['a']);
+        let s = JsonValue::String("a".into());
         assert!(s.is_string());
-        assert_eq!(s.as_string(), Some(&['a'][..]));
+        assert_eq!(s.as_str(), Some("a"));
+        #[allow(deprecated)]
+        {
+            assert_eq!(s.as_string(), Some("a"));
+            assert_eq!(s.to_chars(), Some(vec!['a']));
+            assert_eq!(s.clone().to_string(), Some(vec!['a']));
+            assert_eq!(JsonValue::Null.to_chars(), None);
+            assert_eq!(JsonValue::Null.to_string(), None);
+        }
         assert_eq!(s.as_object(), None);
         assert_eq!(s.as_bool(), None);
         assert_eq!(s.as_number(), None);
         assert_eq!(s.as_array(), None);
-        assert_eq!(s.clone().to_string(), Some(vec!['a']));
+        assert_eq!(s.clone().into_string(), Some("a".into()));
         assert_eq!(s.clone().to_object(), None);
         assert_eq!(s.clone().to_bool(), None);
         assert_eq!(s.clone().to_number(), None);
         assert_eq!(s.clone().to_array(), None);
 
-        let n = JsonValue::Number(NumberValue {
-            integer: 0,
-            fraction: 0,
-            fraction_length: 0,
-            exponent: 0,
-            negative: false,
-        });
+        let n = JsonValue::Number(NumberValue::new(0, 0, 0, 0, false));
         assert!(n.is_number());
-        assert_eq!(
-            n.as_number(),
-            Some(&NumberValue {
-                integer: 0,
-                fraction: 0,
-                fraction_length: 0,
-                exponent: 0,
-                negative: false,
-            }),
-        );
+        assert_eq!(n.as_number(), Some(&NumberValue::new(0, 0, 0, 0, false)),);
         assert_eq!(n.as_object(), None);
         assert_eq!(n.as_bool(), None);
-        assert_eq!(n.as_string(), None);
+        assert_eq!(n.as_str(), None);
         assert_eq!(n.as_array(), None);
         assert_eq!(
             n.clone().to_number(),
-            Some(NumberValue {
-                integer: 0,
-                fraction: 0,
-                fraction_length: 0,
-                exponent: 0,
-                negative: false,
-            }),
+            Some(NumberValue::new(0, 0, 0, 0, false)),
         );
         assert_eq!(n.clone().to_object(), None);
         assert_eq!(n.clone().to_bool(), None);
-        assert_eq!(n.clone().to_string(), None);
+        assert_eq!(n.clone().into_string(), None);
         assert_eq!(n.clone().to_array(), None);
 
         let b = JsonValue::Boolean(false);
@@ -404,12 +1267,12 @@ mod tests {
         assert_eq!(b.as_bool(), Some(&false));
         assert_eq!(b.as_object(), None);
         assert_eq!(b.as_number(), None);
-        assert_eq!(b.as_string(), None);
+        assert_eq!(b.as_str(), None);
         assert_eq!(b.as_array(), None);
         assert_eq!(b.clone().to_bool(), Some(false));
         assert_eq!(b.clone().to_object(), None);
         assert_eq!(b.clone().to_number(), None);
-        assert_eq!(b.clone().to_string(), None);
+        assert_eq!(b.clone().into_string(), None);
         assert_eq!(b.clone().to_array(), None);
 
         let null = JsonValue::Null;
@@ -418,95 +1281,47 @@ mod tests {
         assert_eq!(null.as_bool(), None);
         assert_eq!(null.as_number(), None);
         assert_eq!(null.as_object(), None);
-        assert_eq!(null.as_string(), None);
+        assert_eq!(null.as_str(), None);
         assert_eq!(null.clone().to_array(), None);
         assert_eq!(null.clone().to_bool(), None);
         assert_eq!(null.clone().to_number(), None);
         assert_eq!(null.clone().to_object(), None);
-        assert_eq!(null.clone().to_string(), None);
+        assert_eq!(null.clone().into_string(), None);
     }
 
     #[test]
     fn serialize_number_value() {
-        let val = NumberValue {
-            integer: 1234,
-            fraction: 0,
-            fraction_length: 0,
-            exponent: 0,
-            negative: false,
-        };
+        let val = NumberValue::new(1234, 0, 0, 0, false);
         assert_eq!(val.serialize(), b"1234");
 
-        let val = NumberValue {
-            integer: 1234,
-            fraction: 0,
-            fraction_length: 0,
-            exponent: 0,
-            negative: true,
-        };
+        let val = NumberValue::new(1234, 0, 0, 0, true);
         assert_eq!(val.serialize(), b"-1234");
 
-        let val = NumberValue {
-            integer: 1234,
-            fraction: 5678,
-            fraction_length: 4,
-            exponent: 0,
-            negative: true,
-        };
+        let val = NumberValue::new(1234, 5678, 4, 0, true);
         assert_eq!(val.serialize(), b"-1234.5678");
 
-        let val = NumberValue {
-            integer: 1234,
-            fraction: 1,
-            fraction_length: 3,
-            exponent: 0,
-            negative: false,
-        };
+        let val = NumberValue::new(1234, 1, 3, 0, false);
         assert_eq!(val.serialize(), b"1234.001");
 
-        let val = NumberValue {
-            integer: 1234,
-            fraction: 0,
-            fraction_length: 0,
-            exponent: 3,
-            negative: false,
-        };
+        let val = NumberValue::new(1234, 0, 0, 3, false);
         assert_eq!(val.serialize(), b"1234e3");
 
-        let val = NumberValue {
-            integer: 1234,
-            fraction: 0,
-            fraction_length: 0,
-            exponent: -5,
-            negative: false,
-        };
+        let val = NumberValue::new(1234, 0, 0, -5, false);
         assert_eq!(val.serialize(), b"1234e-5");
 
-        let val = NumberValue {
-            integer: 1234,
-            fraction: 56,
-            fraction_length: 4,
-            exponent: -5,
-            negative: false,
-        };
+        let val = NumberValue::new(1234, 56, 4, -5, false);
         assert_eq!(val.serialize(), b"1234.0056e-5");
 
-        let val = NumberValue {
-            integer: 1234,
-            fraction: 5,
-            fraction_length: 2,
-            exponent: 5,
-            negative: true,
-        };
+        let val = NumberValue::new(1234, 5, 2, 5, true);
         assert_eq!(val.serialize(), b"-1234.05e5");
     }
 
     #[test]
     fn serialize_works() {
-        let obj = JsonValue::Object(vec![(
-            "test\"123".chars().into_iter().collect(),
+        let obj = JsonValue::Object(JsonObject::from_members(vec![(
+            "test\"123".into(),
             JsonValue::Null,
-        )]);
+        )]));
         assert_eq!(
             std::str::from_utf8(&obj.format(4)[..]).unwrap(),
             r#"{
@@ -514,43 +1329,25 @@ mod tests {
 }"#
         );
 
-        let obj = JsonValue::Object(vec![
+        let obj = JsonValue::Object(JsonObject::from_members(vec![
             (
-                vec!['t', 'e', 's', 't'],
-                JsonValue::Number(NumberValue {
-                    integer: 123,
-                    fraction: 4,
-                    fraction_length: 2,
-                    exponent: 0,
-                    negative: false,
-                }),
+                "test".into(),
+                JsonValue::Number(NumberValue::new(123, 4, 2, 0, false)),
             ),
             (
-                vec!['t', 'e', 's', 't', '2'],
+                "test2".into(),
                 JsonValue::Array(vec![
-                    JsonValue::Number(NumberValue {
-                        integer: 1,
-                        fraction: 0,
-                        fraction_length: 0,
-                        exponent: -4,
-                        negative: false,
-                    }),
-                    JsonValue::Number(NumberValue {
-                        integer: 2,
-                        fraction: 41,
-                        fraction_length: 3,
-                        exponent: 2,
-                        negative: false,
-                    }),
+                    JsonValue::Number(NumberValue::new(1, 0, 0, -4, false)),
+                    JsonValue::Number(NumberValue::new(2, 41, 3, 2, false)),
                     JsonValue::Boolean(true),
                     JsonValue::Boolean(false),
                     JsonValue::Null,
-                    JsonValue::String(vec!['\"', '1', 'n', '\"']),
-                    JsonValue::Object(vec![]),
+                    JsonValue::String("\"1n\"".into()),
+                    JsonValue::Object(JsonObject::from_members(vec![])),
                     JsonValue::Array(vec![]),
                 ]),
             ),
-        ]);
+        ]));
 
         assert_eq!(
             std::str::from_utf8(&obj.format(4)[..]).unwrap(),
@@ -579,64 +1376,490 @@ mod tests {
     fn to_f64_works() {
         use assert_float_eq::*;
 
-        assert_f64_near!(
-            NumberValue {
-                integer: 1,
-                fraction: 5,
-                fraction_length: 1,
-                exponent: 0,
-                negative: true,
-            }
-            .to_f64(),
-            -1.5
+        assert_f64_near!(NumberValue::new(1, 5, 1, 0, true).to_f64(), -1.5);
+
+        assert_f64_near!(NumberValue::new(0, 5, 1, 0, true).to_f64(), -0.5);
+
+        assert_f64_near!(NumberValue::new(0, 5, 1, 0, false).to_f64(), 0.5);
+
+        assert_f64_near!(NumberValue::new(1, 15, 3, 1, false).to_f64(), 10.15);
+
+        assert_f64_near!(NumberValue::new(1, 15, 3, -1, true).to_f64(), -0.1015);
+    }
+
+    #[test]
+    fn to_f64_rounds_correctly() {
+        let number = |input: &str| match crate::parse_json(input) {
+            Ok(JsonValue::Number(n)) => n,
+            other => panic!("{:?}", other),
+        };
+        for input in [
+            "0.1",
+            "0.3",
+            "123.456",
+            "2.2250738585072014e-308",
+            "1.7976931348623157e308",
+            "4.9e-324",
+            "9007199254740993",
+            "0.000000000000000000000000000000000000000000000000000000000000000000000000001",
+            "8.98846567431158e307",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                number(input).to_f64(),
+                input.parse::<f64>().unwrap(),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(number("1e400").to_f64(), f64::INFINITY);
+        assert_eq!(number("-1e400").to_f64(), f64::NEG_INFINITY);
+        assert_eq!(number("1e-400").to_f64(), 0.0);
+        assert_eq!(f64::from(number("-2.5")), -2.5);
+    }
+
+    #[test]
+    fn from_float_is_shortest() {
+        let text = |value: f64| NumberValue::try_from(value).unwrap().serialize();
+        assert_eq!(text(0.1), b"0.1");
+        assert_eq!(text(-0.0), b"-0");
+        assert_eq!(text(1.0), b"1");
+        assert_eq!(text(100.0), b"100");
+        assert_eq!(text(123.456), b"123.456");
+        assert_eq!(text(0.000001), b"0.000001");
+        assert_eq!(text(1e-7), b"1e-7");
+        assert_eq!(text(1e300), b"1e300");
+        assert_eq!(text(-1.5e-300), b"-1.5e-300");
+        assert_eq!(text(1e18), b"1000000000000000000");
+        assert_eq!(text(1e19), b"1e19");
+        assert_eq!(text(f64::MAX), b"1.7976931348623157e308");
+        assert_eq!(text(5e-324), b"5e-324");
+        assert_eq!(NumberValue::try_from(0.1f32).unwrap().serialize(), b"0.1");
+        assert_eq!(
+            NumberValue::try_from(16777216f32).unwrap().serialize(),
+            b"16777216"
         );
+        assert_eq!(NumberValue::try_from(f64::NAN), Err(NotFinite));
+        assert_eq!(NumberValue::try_from(f64::INFINITY), Err(NotFinite));
+        assert_eq!(NumberValue::try_from(f32::NEG_INFINITY), Err(NotFinite));
 
-        assert_f64_near!(
-            NumberValue {
-                integer: 0,
-                fraction: 5,
-                fraction_length: 1,
-                exponent: 0,
-                negative: true,
+        fn round_trip(value: f64) -> bool {
+            match NumberValue::try_from(value) {
+                Ok(number) => {
+                    let parsed =
+                        crate::parse_json(core::str::from_utf8(&number.serialize()).unwrap());
+                    number.to_f64().to_bits() == value.to_bits()
+                        && parsed == Ok(JsonValue::Number(number))
+                }
+                Err(NotFinite) => !value.is_finite(),
             }
-            .to_f64(),
-            -0.5
-        );
+        }
+        quickcheck::quickcheck(round_trip as fn(f64) -> bool);
+    }
 
-        assert_f64_near!(
-            NumberValue {
-                integer: 0,
-                fraction: 5,
-                fraction_length: 1,
-                exponent: 0,
-                negative: false,
+    // Depends on duplicate keys being kept.
+    #[test]
+    fn it_looks_up_members() {
+        let mut value =
+            crate::parse_json(r#"{"a": 1, "b": [true, {"c": null}], "a": 2, "\u00e9": "x"}"#)
+                .unwrap();
+        assert_eq!(value.get("a"), Some(&crate::json!(1)));
+        assert_eq!(value.get("é"), Some(&crate::json!("x")));
+        assert_eq!(value.get("c"), None);
+        assert!(value.contains_key("b"));
+        assert!(!value.contains_key("B"));
+        assert_eq!(value["b"][0], JsonValue::Boolean(true));
+        assert_eq!(value["b"][1]["c"], JsonValue::Null);
+        assert_eq!(value["b"][1]["missing"], JsonValue::Null);
+        assert_eq!(value["b"][5], JsonValue::Null);
+        assert_eq!(value["a"][0], JsonValue::Null);
+        assert_eq!(value["b"].get_index(1), Some(&crate::json!({"c": null})));
+        assert_eq!(value.get_index(0), None);
+        assert_eq!(value.len(), 4);
+        assert_eq!(value["b"].len(), 2);
+        assert!(JsonValue::Null.is_empty());
+
+        let keys: Vec<std::string::String> = value.keys().map(Into::into).collect();
+        assert_eq!(keys, ["a", "b", "a", "é"]);
+        assert_eq!(value.values().nth(2), Some(&crate::json!(2)));
+        assert_eq!(JsonValue::Null.keys().count(), 0);
+
+        *value.get_mut("a").unwrap() = crate::json!([]);
+        assert_eq!(value["a"], crate::json!([]));
+        assert_eq!(value.get_mut("z"), None);
+    }
+
+    #[test]
+    fn it_mutates_members() {
+        let mut value = crate::parse_json(r#"{"a": 1, "b": 2, "a": 3}"#).unwrap();
+        assert_eq!(value.insert("a", 4), Some(crate::json!(1)));
+        assert_eq!(value.insert("c", "x"), None);
+        assert_eq!(value, crate::json!({"a": 4, "b": 2, "c": "x"}));
+        assert_eq!(value.get("a"), Some(&crate::json!(4)));
+
+        assert_eq!(value.remove("a"), Some(crate::json!(4)));
+        assert_eq!(value.remove("a"), None);
+        assert_eq!(value, crate::json!({"b": 2, "c": "x"}));
+
+        *value.entry("b").or_insert(0) = crate::json!(5);
+        value
+            .entry("n")
+            .and_modify(|_| unreachable!())
+            .or_insert_with(|| crate::json!([]))
+            .push(true);
+        value.entry("n").and_modify(|n| n.push(false)).or_insert(0);
+        assert_eq!(value, crate::json!({"b": 5, "c": "x", "n": [true, false]}));
+
+        value.retain(|k, v| {
+            if k == "n" {
+                v.push(JsonValue::Null);
             }
-            .to_f64(),
-            0.5
+            k != "c"
+        });
+        assert_eq!(value, crate::json!({"b": 5, "n": [true, false, null]}));
+
+        assert_eq!(value["n"].clone().take(), crate::json!([true, false, null]));
+        let n = value.get_mut("n").unwrap().take();
+        assert_eq!(n.len(), 3);
+        assert_eq!(value, crate::json!({"b": 5, "n": null}));
+
+        let mut value = JsonValue::Null;
+        value.insert("a", 1);
+        value.get_mut("a").unwrap().take();
+        value.entry("b").or_insert(JsonValue::Null).push("x");
+        assert_eq!(value, crate::json!({"a": null, "b": ["x"]}));
+        assert_eq!(crate::json!(1).remove("a"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "JsonValue is not an object")]
+    fn insert_panics_on_non_objects() {
+        crate::json!([]).insert("a", 1);
+    }
+
+    #[test]
+    fn number_integer_accessors() {
+        let number = |input: &str| match crate::parse_json(input) {
+            Ok(JsonValue::Number(n)) => n,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(number("0").to_u64(), Some(0));
+        assert_eq!(number("-0").to_u64(), Some(0));
+        assert_eq!(number("0e999").to_u8(), Some(0));
+        assert_eq!(number("18446744073709551615").to_u64(), Some(u64::MAX));
+        assert_eq!(number("18446744073709551615").to_i64(), None);
+        assert_eq!(number("-9223372036854775808").to_i64(), Some(i64::MIN));
+        assert_eq!(number("-9223372036854775809").to_i64(), None);
+        assert_eq!(number("1.5e1").to_u32(), Some(15));
+        assert_eq!(number("1.50").to_u32(), None);
+        assert_eq!(number("2.000").to_u32(), Some(2));
+        assert_eq!(number("1500e-2").to_i32(), Some(15));
+        assert_eq!(number("1501e-2").to_i32(), None);
+        assert_eq!(number("-12").to_u32(), None);
+        assert_eq!(number("-12").to_i8(), Some(-12));
+        assert_eq!(number("256").to_u8(), None);
+        assert_eq!(number("255").to_u8(), Some(255));
+        assert_eq!(number("-128").to_i8(), Some(i8::MIN));
+        assert_eq!(number("1e20").to_u64(), None);
+        assert_eq!(number("1e20").to_u128(), Some(100_000_000_000_000_000_000));
+        assert_eq!(number("1e38").to_u128(), Some(10u128.pow(38)));
+        assert_eq!(number("1e39").to_u128(), None);
+        assert_eq!(number("-1e38").to_i128(), Some(-(10i128.pow(38))));
+        assert_eq!(number("1e-1").to_i64(), None);
+        assert_eq!(number("12345678901234567890e-19").to_i64(), None);
+        assert_eq!(
+            number("1234567890123456789.0e1").to_i128(),
+            Some(12345678901234567890)
         );
+        assert_eq!(number("1e-40").to_i64(), None);
+        assert_eq!(number("1e400").to_i128(), None);
+    }
 
-        assert_f64_near!(
-            NumberValue {
-                integer: 1,
-                fraction: 15,
-                fraction_length: 3,
-                exponent: 1,
-                negative: false,
-            }
-            .to_f64(),
-            10.15
+    #[test]
+    fn number_fractions_are_exact() {
+        let number = |input: &str| match crate::parse_json(input) {
+            Ok(JsonValue::Number(n)) => n,
+            other => panic!("{:?}", other),
+        };
+        for input in [
+            "1.0",
+            "-1.000",
+            "0.00100",
+            "0.0000000000000000000000001",
+            "1.5000000000000000000",
+            "12.34e-5",
+            "0.1000000000000000000000000",
+            "0.12345678901234567890123",
+            "1.10000000000000000000000",
+            "-5.000000000000000000000000000000001e-7",
+        ]
+        .iter()
+        {
+            assert_eq!(number(input).serialize(), input.as_bytes());
+        }
+        assert_eq!(number("1.10000000000000000000000"), number("1.1"));
+        assert_eq!(
+            number("0.12345678901234567890123").to_decimal(),
+            Some((12345678901234567890123, 23))
         );
+        assert_eq!(
+            number("0.12345678901234567890123").to_f64(),
+            0.12345678901234568
+        );
+
+        assert_eq!(number("1.0"), number("1"));
+        assert_eq!(number("1"), number("10e-1"));
+        assert_eq!(number("0.00100"), number("1e-3"));
+        assert_eq!(number("-0.0"), number("0e5"));
+        assert_eq!(number("1500"), number("1.5e3"));
+        assert_ne!(number("1.01"), number("1.1"));
+        assert_ne!(number("-1"), number("1"));
+        assert_ne!(number("1e-20"), number("1e20"));
+        assert_eq!(
+            crate::parse_json("[1.0, {\"a\": 2}]"),
+            crate::parse_json("[1, {\"a\": 2.00}]")
+        );
+
+        let normalized = |input: &str| {
+            let n = number(input).normalize();
+            (
+                n.integer,
+                n.fraction,
+                n.fraction_length,
+                n.exponent,
+                n.negative,
+            )
+        };
+        assert_eq!(normalized("1.000"), (1, 0, 0, 0, false));
+        assert_eq!(normalized("1500"), (15, 0, 0, 2, false));
+        assert_eq!(normalized("-12.50e3"), (125, 0, 0, 2, true));
+        assert_eq!(normalized("0.00100"), (1, 0, 0, -3, false));
+        assert_eq!(normalized("-0.0"), (0, 0, 0, 0, false));
+        assert_eq!(
+            normalized("18446744073709551615.5"),
+            (u64::MAX, 5, 1, 0, false)
+        );
+        assert_eq!(
+            number("18446744073709551615.5").normalize(),
+            number("18446744073709551615.5")
+        );
+    }
+
+    #[test]
+    fn it_handles_fractions_longer_than_fraction_length() {
+        let short = NumberValue::new(1, 10, 0, 0, false);
+        assert!(short == short.clone());
+        assert_eq!(short.serialize(), b"1");
+
+        let short = NumberValue::new(1, 123, 2, 0, false);
+        assert_eq!(short.serialize(), b"1.123");
+        assert!(short == short.clone());
+    }
+
+    #[test]
+    fn it_escapes_strings() {
+        let value = JsonValue::String("\"\\/\x08\x0c\n\r\t\x00\x1f\x7f é😀".into());
+        assert_eq!(
+            std::str::from_utf8(&value.serialize()).unwrap(),
+            "\"\\\"\\\\/\\b\\f\\n\\r\\t\\u0000\\u001f\x7f é😀\""
+        );
+    }
+
+    #[test]
+    fn it_writes_unescaped_runs_as_slices() {
+        struct Writes(Vec<std::string::String>);
+
+        impl Sink for Writes {
+            type Error = core::convert::Infallible;
 
-        assert_f64_near!(
-            NumberValue {
-                integer: 1,
-                fraction: 15,
-                fraction_length: 3,
-                exponent: -1,
-                negative: true,
+            fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+                if !s.is_empty() {
+                    self.0.push(s.into());
+                }
+                Ok(())
             }
-            .to_f64(),
-            -0.1015
+        }
+
+        let mut writes = Writes(Vec::new());
+        write_string(&mut writes, "plain é😀 \"quoted\"\n", &Default::default()).unwrap();
+        assert_eq!(
+            writes.0,
+            ["\"", "plain é😀 ", "\\\"", "quoted", "\\\"", "\\n", "\""]
+        );
+    }
+
+    #[test]
+    fn it_escapes_with_options() {
+        let value = JsonValue::String("<a href='x'>&\u{2028}\u{2029}é€😀".into());
+        let serialize = |ascii_only, html_safe| {
+            let options = FormatOptions {
+                ascii_only,
+                html_safe,
+                ..Default::default()
+            };
+            std::string::String::from_utf8(value.serialize_with(&options)).unwrap()
+        };
+        assert_eq!(
+            serialize(false, false),
+            "\"<a href='x'>&\u{2028}\u{2029}é€😀\""
+        );
+        assert_eq!(
+            serialize(true, false),
+            r#""<a href='x'>&\u2028\u2029\u00e9\u20ac\ud83d\ude00""#
+        );
+        assert_eq!(
+            serialize(false, true),
+            "\"\\u003ca href='x'\\u003e\\u0026\\u2028\\u2029é€😀\""
+        );
+        assert_eq!(
+            serialize(true, true),
+            r#""\u003ca href='x'\u003e\u0026\u2028\u2029\u00e9\u20ac\ud83d\ude00""#
+        );
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        fn round_trip(s: std::string::String) -> bool {
+            let value = JsonValue::Object(JsonObject::from_members(vec![(
+                s.clone(),
+                JsonValue::String(s),
+            )]));
+            let escaped = FormatOptions {
+                ascii_only: true,
+                html_safe: true,
+                ..Default::default()
+            };
+            [Default::default(), escaped].iter().all(|options| {
+                let serialized = value.serialize_with(options);
+                let text = std::str::from_utf8(&serialized).unwrap();
+                crate::parse_json(text) == Ok(value.clone())
+                    && crate::parse_json_borrowed(text).map(JsonValue::from) == Ok(value.clone())
+            })
+        }
+        quickcheck::quickcheck(round_trip as fn(std::string::String) -> bool);
+    }
+
+    // The snapshots depend on members staying in document order.
+    const SNAPSHOT_INPUT: &str = r#"{"name":"lite","tags":["a","b"],"nested":[[1,2],[3,[4,5]]],"empty":{},"list":[],"obj":{"x":null,"y":[true,false]}}"#;
+
+    fn format_snapshot(options: &FormatOptions) -> std::string::String {
+        let value = crate::parse_json(SNAPSHOT_INPUT).unwrap();
+        std::string::String::from_utf8(value.serialize_with(options)).unwrap()
+    }
+
+    #[test]
+    fn it_formats_with_spaces() {
+        assert_eq!(format_snapshot(&Default::default()), SNAPSHOT_INPUT);
+        assert_eq!(
+            format_snapshot(&FormatOptions::pretty(2)),
+            r#"{
+  "name": "lite",
+  "tags": [
+    "a",
+    "b"
+  ],
+  "nested": [
+    [
+      1,
+      2
+    ],
+    [
+      3,
+      [
+        4,
+        5
+      ]
+    ]
+  ],
+  "empty": {},
+  "list": [],
+  "obj": {
+    "x": null,
+    "y": [
+      true,
+      false
+    ]
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn it_formats_with_tabs_and_crlf() {
+        let options = FormatOptions {
+            indent: 1,
+            use_tabs: true,
+            trailing_newline: true,
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_snapshot(&options),
+            r#"{
+	"name":"lite",
+	"tags":[
+		"a",
+		"b"
+	],
+	"nested":[
+		[
+			1,
+			2
+		],
+		[
+			3,
+			[
+				4,
+				5
+			]
+		]
+	],
+	"empty":{},
+	"list":[],
+	"obj":{
+		"x":null,
+		"y":[
+			true,
+			false
+		]
+	}
+}
+"#
+            .replace('\n', "\r\n")
+        );
+    }
+
+    #[test]
+    fn it_formats_short_containers_inline() {
+        let options = FormatOptions {
+            max_width: Some(30),
+            ..FormatOptions::pretty(2)
+        };
+        assert_eq!(
+            format_snapshot(&options),
+            r#"{
+  "name": "lite",
+  "tags": ["a", "b"],
+  "nested": [
+    [1, 2],
+    [3, [4, 5]]
+  ],
+  "empty": {},
+  "list": [],
+  "obj": {
+    "x": null,
+    "y": [true, false]
+  }
+}"#
+        );
+        let options = FormatOptions {
+            max_width: Some(200),
+            ..FormatOptions::pretty(2)
+        };
+        assert_eq!(
+            format_snapshot(&options),
+            r#"{"name": "lite", "tags": ["a", "b"], "nested": [[1, 2], [3, [4, 5]]], "empty": {}, "list": [], "obj": {"x": null, "y": [true, false]}}"#
         );
     }
 }
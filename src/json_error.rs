@@ -0,0 +1,199 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec, vec::Vec};
+
+use core::fmt::{self, Display, Formatter};
+use lite_parser::{
+    impls::{render_snippet, BytePosition, SimplePosition},
+    traits::Input,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    /// A character that cannot appear at this point of the document.
+    UnexpectedChar,
+    /// The input ended before the document was complete.
+    UnexpectedEof,
+    /// A backslash in a string is not followed by a valid escape sequence, or a
+    /// `\u` escape is an unpaired surrogate.
+    InvalidEscape,
+    /// A number does not fit into `NumberValue` and `ParserOptions::number_overflow`
+    /// is `NumberOverflow::Error`.
+    NumberOverflow,
+    /// Objects and arrays are nested deeper than `ParserOptions::max_nest_level`.
+    NestingTooDeep,
+    /// The document is followed by something other than whitespace.
+    TrailingCharacters,
+    /// The input is not valid UTF-8.
+    InvalidUtf8,
+}
+
+/// Error returned when parsing JSON fails.
+#[cfg_attr(feature = "std", derive(PartialEq, Eq))]
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    pub kind: JsonErrorKind,
+    /// Position of the furthest point the parser reached.
+    pub position: SimplePosition,
+    /// The character at `position`, or `None` at the end of input.
+    pub found: Option<char>,
+    /// The tokens that would have been accepted at `position`.
+    pub expected: Vec<&'static str>,
+    /// The reason recorded by the parser that failed.
+    pub reasons: Vec<(SimplePosition, &'static str)>,
+}
+
+/// A parse failure, recorded where parsing stopped.
+#[derive(Debug, Clone)]
+pub(crate) struct Failure<P = BytePosition> {
+    pub(crate) position: P,
+    pub(crate) kind: JsonErrorKind,
+    pub(crate) expected: Vec<&'static str>,
+    pub(crate) reason: &'static str,
+}
+
+impl<P> Failure<P> {
+    /// An unexpected character where one of `expected` should have been.
+    pub(crate) fn new(position: P, reason: &'static str, expected: Vec<&'static str>) -> Self {
+        Self {
+            position,
+            kind: JsonErrorKind::UnexpectedChar,
+            expected,
+            reason,
+        }
+    }
+
+    pub(crate) fn with_kind(self, kind: JsonErrorKind) -> Self {
+        Self { kind, ..self }
+    }
+}
+
+impl JsonError {
+    /// Locates `failure` in `input`. The end of input or invalid UTF-8 at the
+    /// failure position is reported in place of an unexpected character.
+    pub(crate) fn new(input: &[u8], failure: Failure) -> Self {
+        let position = failure.position;
+        let (kind, found) = match input.next(position) {
+            Ok((c, _)) => (failure.kind, Some(c)),
+            Err(_) if input.is_end(position) => (JsonErrorKind::UnexpectedEof, None),
+            Err(_) => (JsonErrorKind::InvalidUtf8, None),
+        };
+        Self {
+            kind,
+            position: position.into(),
+            found,
+            expected: failure.expected,
+            reasons: vec![(position.into(), failure.reason)],
+        }
+    }
+
+    /// Renders the error followed by the offending line of `source` with a
+    /// caret under the column where parsing failed.
+    pub fn render(&self, source: &str) -> String {
+        render_snippet(self, source, self.position.line, self.position.column)
+    }
+}
+
+impl Display for JsonErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JsonErrorKind::UnexpectedChar => "unexpected character",
+            JsonErrorKind::UnexpectedEof => "unexpected end of input",
+            JsonErrorKind::InvalidEscape => "invalid escape sequence",
+            JsonErrorKind::NumberOverflow => "number out of range",
+            JsonErrorKind::NestingTooDeep => "nesting too deep",
+            JsonErrorKind::TrailingCharacters => "trailing characters",
+            JsonErrorKind::InvalidUtf8 => "invalid UTF-8",
+        })
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(c) = self.found {
+            write!(f, " {:?}", c)?;
+        }
+        write!(
+            f,
+            " at line {}, column {}",
+            self.position.line + 1,
+            self.position.column + 1
+        )?;
+        for (i, token) in self.expected.iter().enumerate() {
+            let sep = match i {
+                0 if self.expected.len() > 2 => ", expected one of ",
+                0 => ", expected ",
+                _ if self.expected.len() == 2 => " or ",
+                _ => ", ",
+            };
+            f.write_str(sep)?;
+            if token.len() > 1 && token.chars().any(|c| c.is_ascii_alphabetic()) {
+                f.write_str(token)?;
+            } else {
+                write!(f, "`{}`", token)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::json_parser::parse_json;
+    use lite_parser::impls::{SimpleError, SimplePosition};
+
+    #[test]
+    fn it_displays_errors() {
+        let source = "{\n\t\"a\": [1, 2 x]\n}";
+        let error = parse_json(source).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unexpected character 'x' at line 2, column 13, expected `,` or `]`"
+        );
+        assert_eq!(
+            error.render(source),
+            "error: unexpected character 'x' at line 2, column 13, expected `,` or `]`\n  |\n2 | \t\"a\": [1, 2 x]\n  | \t           ^"
+        );
+
+        assert_eq!(
+            parse_json("[").unwrap_err().to_string(),
+            "unexpected end of input at line 1, column 2, expected one of `{`, `[`, string, number, true, false, null, `]`"
+        );
+        assert_eq!(
+            parse_json("[tru]").unwrap_err().to_string(),
+            "unexpected character ']' at line 1, column 5, expected `e`"
+        );
+
+        let error = SimpleError {
+            reasons: vec![
+                (
+                    SimplePosition {
+                        index: 3,
+                        line: 0,
+                        column: 3,
+                    },
+                    "Value",
+                ),
+                (
+                    SimplePosition {
+                        index: 0,
+                        line: 0,
+                        column: 0,
+                    },
+                    "Array",
+                ),
+            ],
+        };
+        assert_eq!(error.to_string(), "Value at line 1, column 4, in Array");
+        assert_eq!(
+            error.render("[1 2]"),
+            "error: Value at line 1, column 4, in Array\n  |\n1 | [1 2]\n  |    ^"
+        );
+    }
+}
@@ -2,21 +2,26 @@
 extern crate alloc;
 
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{borrow::Cow, string::String as StdString, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::{borrow::Cow, string::String as StdString};
 
-use crate::json::{JsonObject, JsonValue, NumberValue};
+use crate::json::{GenericJsonValue, JsonValue, JsonValueRef, NumberValue};
+use crate::json_error::{Failure, JsonError, JsonErrorKind};
+use crate::json_reader::{ReaderState, Scalar, Text, Token};
+use crate::object::{ObjectBackend, ObjectMap};
+use crate::traits::JsonHandler;
 use lite_parser::{
-    impls::SimpleError,
     literals,
     parser::{
-        Concat, Concat3, Either, OneOf, OneOrMore, Parser, ParserContext, ParserOptions,
-        ZeroOrMore, ZeroOrOne,
+        Concat, Concat3, Either, LoneSurrogate, NumberOverflow, OneOf, OneOrMore, Parser,
+        ParserContext, ParserOptions, ZeroOrMore, ZeroOrOne,
     },
     parsers,
     traits::{Error, Input, ResultOf},
 };
 
-use core::convert::TryInto;
+use core::convert::TryFrom;
 
 literals! {
     pub WhitespaceChar => '\u{0020}' | '\u{000D}' | '\u{000A}' | '\u{0009}';
@@ -42,85 +47,237 @@ pub type Sign = ZeroOrOne<SignChar>;
 pub type Digits = OneOrMore<DigitChar>;
 
 parsers! {
-    pub PositiveInteger = OneOf<Concat<OneToNineChar, Digits>, DigitChar>, u64, (output) => {
+    pub PositiveInteger = OneOf<Concat<OneToNineChar, Digits>, DigitChar>, Vec<char>, (output) => {
         match output {
-            Either::A((c, cs)) => {
-                let mut val = c.to_digit(10).unwrap() as u64;
-                for c in cs {
-                    val *= 10;
-                    val += c.to_digit(10).unwrap() as u64;
-                }
-                val
+            Either::A((c, mut cs)) => {
+                cs.insert(0, c);
+                cs
             },
-            Either::B(c) => c.to_digit(10).unwrap() as u64,
+            Either::B(c) => vec![c],
         }
     };
 
-    pub NegativeInteger = Concat<NegativeSignChar, PositiveInteger>, u64, (output) => {
+    pub NegativeInteger = Concat<NegativeSignChar, PositiveInteger>, Vec<char>, (output) => {
         let (_, output) = output;
         output
     };
 
-    pub Integer = OneOf<PositiveInteger, NegativeInteger>, (bool, u64), (output) => {
+    pub Integer = OneOf<PositiveInteger, NegativeInteger>, (bool, Vec<char>), (output) => {
         match output {
             Either::A(a) => (false, a),
             Either::B(b) => (true, b),
         }
     };
 
-    pub Fraction = ZeroOrOne<Concat<DotChar, Digits>>, (u64, u32), (output) => {
+    pub Fraction = ZeroOrOne<Concat<DotChar, Digits>>, Vec<char>, (output) => {
         match output {
-            Either::A((_, cs)) => {
-                let mut val = 0u64;
-                let len = cs.len();
-                for c in cs {
-                    val *= 10u64;
-                    val += c.to_digit(10).unwrap() as u64;
-                }
-                (val, len as u32)
-            },
-            Either::B(_) => (0u64, 0u32),
+            Either::A((_, cs)) => cs,
+            Either::B(_) => Vec::new(),
         }
     };
 
-    pub Exponent = ZeroOrOne<Concat3<EChar, Sign, Digits>>, i32, (output) => {
+    pub Exponent = ZeroOrOne<Concat3<EChar, Sign, Digits>>, Option<(char, Option<char>, Vec<char>)>, (output) => {
         match output {
-            Either::A((_, (s, cs))) => {
-                let mul = if let Either::A('-') = s { -1 } else { 1 };
-                let mut val = 0i32;
-                for c in cs {
-                    val *= 10;
-                    val += c.to_digit(10).unwrap() as i32;
-                }
-                val * mul
-            },
-            Either::B(_) => 0,
-        }
-    };
-
-    pub Number = Concat3<Integer, Fraction, Exponent>, NumberValue, (output) => {
-        let ((s, n), (f, e)) = output;
-        NumberValue {
-            integer: n,
-            fraction: f.0,
-            fraction_length: f.1,
-            exponent: e,
-            negative: s,
+            Either::A((e, (s, cs))) => Some((e, match s {
+                Either::A(s) => Some(s),
+                Either::B(_) => None,
+            }, cs)),
+            Either::B(_) => None,
         }
     };
 
     pub Hex = HexChar, u8, (output) => {
         output.to_digit(16).unwrap() as u8
     };
+}
 
-    pub String = Concat3<DoubleQuoteChar, Characters, DoubleQuoteChar>, Vec<char>, (output) => {
-        match (output.1).0 {
-            Either::A(bytes) => bytes,
-            Either::B(_) => Vec::new(),
+/// Appends decimal `digits` to `value`, returning how many of them fit before it
+/// would overflow.
+fn push_digits(value: &mut u64, digits: &str) -> usize {
+    for (i, b) in digits.bytes().enumerate() {
+        match value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add((b - b'0') as u64))
+        {
+            Some(v) => *value = v,
+            None => return i,
+        }
+    }
+    digits.len()
+}
+
+/// Builds a `NumberValue` from the text of a valid JSON number, such as `-1.50E+2`,
+/// or returns `None` if its digits overflow and `policy` is `NumberOverflow::Error`.
+///
+/// Digits that do not fit otherwise make the number keep its text, so that it still
+/// converts and serializes exactly. Fraction digits that do not fit never overflow.
+pub(crate) fn number_value(text: &str, policy: NumberOverflow) -> Option<NumberValue> {
+    let negative = text.starts_with('-');
+    let unsigned = text.trim_start_matches('-');
+    let (digits, exponent_part) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], &unsigned[i + 1..]),
+        None => (unsigned, ""),
+    };
+    let (integer_digits, fraction_digits) = match digits.find('.') {
+        Some(i) => (&digits[..i], &digits[i + 1..]),
+        None => (digits, ""),
+    };
+    let keep_overflow = policy == NumberOverflow::KeepText;
+    let mut integer = 0;
+    let mut fraction = 0;
+    let mut fraction_length = 0;
+    let mut keep_text = cfg!(feature = "arbitrary_precision");
+    // Digits dropped from the integer part, each of which scales the value by ten.
+    let mut scale = 0;
+    let fit = push_digits(&mut integer, integer_digits);
+    if fit < integer_digits.len() {
+        if !keep_overflow {
+            return None;
+        }
+        keep_text = true;
+        scale = (integer_digits.len() - fit) as i64;
+    } else {
+        fraction_length = push_digits(&mut fraction, fraction_digits);
+        keep_text |= fraction_length < fraction_digits.len();
+    }
+    let mut exponent = 0i64;
+    for b in exponent_part.trim_start_matches(['+', '-']).bytes() {
+        exponent = exponent * 10 + (b - b'0') as i64;
+        if exponent > i32::MAX as i64 {
+            if !keep_overflow {
+                return None;
+            }
+            keep_text = true;
+            break;
+        }
+    }
+    if exponent_part.starts_with('-') {
+        exponent = -exponent;
+    }
+    let exponent = match i32::try_from(exponent + scale) {
+        Ok(exponent) => exponent,
+        Err(_) if keep_overflow => {
+            keep_text = true;
+            (exponent + scale).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+        }
+        Err(_) => return None,
+    };
+    let value = NumberValue::new(
+        integer,
+        fraction,
+        fraction_length as u32,
+        exponent,
+        negative,
+    );
+    if keep_text {
+        Some(value.with_lexical(text.into()))
+    } else {
+        Some(value)
+    }
+}
+
+/// The overflow policy in effect for `options`.
+pub(crate) fn number_overflow(options: &ParserOptions) -> NumberOverflow {
+    // Every number keeps its text, so overflowing digits are never lost.
+    if cfg!(feature = "arbitrary_precision") {
+        NumberOverflow::KeepText
+    } else {
+        options.number_overflow
+    }
+}
+
+pub struct Number;
+
+impl<I: Input> Parser<I> for Number {
+    type Output = NumberValue;
+    fn parse(
+        input: &I,
+        current: I::Position,
+        context: &ParserContext,
+    ) -> ResultOf<I, Self::Output> {
+        let (((negative, integer), (fraction, exponent)), next) =
+            <Concat3<Integer, Fraction, Exponent> as Parser<I>>::parse(input, current, context)
+                .map_err(|e| e.add_reason(current, "Number"))?;
+        let (e, sign, digits) = match exponent {
+            Some((e, sign, digits)) => (Some(e), sign, digits),
+            None => (None, None, Vec::new()),
+        };
+        let text: StdString = Some('-')
+            .filter(|_| negative)
+            .into_iter()
+            .chain(integer)
+            .chain(Some('.').filter(|_| !fraction.is_empty()))
+            .chain(fraction)
+            .chain(e)
+            .chain(sign)
+            .chain(digits)
+            .collect();
+        let value = number_value(&text, number_overflow(context.options()))
+            .ok_or_else(|| input.error_at(current, "Number overflow"))?;
+        Ok((value, next))
+    }
+}
+
+/// The characters that may follow a backslash in a string.
+pub(crate) const ESCAPE: &[&str] = &["\"", "\\", "/", "b", "f", "n", "r", "t", "u"];
+
+/// A decoded escape sequence.
+pub(crate) enum Escaped {
+    Char(char),
+    /// A lone surrogate kept by `LoneSurrogate::Preserve`.
+    Surrogate(u16),
+}
+
+/// Decodes the escape sequence starting at `backslash`, whose next character is at
+/// `current`.
+pub(crate) fn decode_escape<I: Input>(
+    input: &I,
+    backslash: I::Position,
+    current: I::Position,
+    options: &ParserOptions,
+) -> Result<(Escaped, I::Position), Failure<I::Position>> {
+    let invalid =
+        Failure::new(current, "Escape", ESCAPE.to_vec()).with_kind(JsonErrorKind::InvalidEscape);
+    let (c, next) = input.next(current).map_err(|_| invalid.clone())?;
+    let c = match c {
+        '"' | '\\' | '/' => c,
+        'b' => '\x08',
+        'f' => '\x0c',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => {
+            let (code, next) = hex4(input, next)?;
+            let code = match code {
+                0xD800..=0xDBFF => match low_surrogate(input, next) {
+                    Some((low, after)) => {
+                        let c = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        return Ok((Escaped::Char(char::from_u32(c).unwrap()), after));
+                    }
+                    None => code,
+                },
+                _ => code,
+            };
+            let escaped = match (char::from_u32(code), options.lone_surrogate) {
+                (Some(c), _) => Escaped::Char(c),
+                (None, LoneSurrogate::Replace) => Escaped::Char(char::REPLACEMENT_CHARACTER),
+                (None, LoneSurrogate::Preserve) => Escaped::Surrogate(code as u16),
+                (None, LoneSurrogate::Error) => {
+                    return Err(
+                        Failure::new(backslash, "Lone surrogate", vec!["surrogate pair"])
+                            .with_kind(JsonErrorKind::InvalidEscape),
+                    )
+                }
+            };
+            return Ok((escaped, next));
         }
+        _ => return Err(invalid),
     };
+    Ok((Escaped::Char(c), next))
 }
 
+/// Parses the escape sequence after a backslash. Lone surrogates kept by
+/// `LoneSurrogate::Preserve` are decoded as U+FFFD REPLACEMENT CHARACTER.
 pub struct Escape;
 
 impl<I: Input> Parser<I> for Escape {
@@ -130,25 +287,55 @@ impl<I: Input> Parser<I> for Escape {
         current: I::Position,
         context: &ParserContext,
     ) -> ResultOf<I, Self::Output> {
-        let (c, next) = input
-            .next(current)
-            .map_err(|e| e.add_reason(current, "Escape"))?;
-        match c {
-            '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => Ok((c, next)),
-            'u' => {
-                let (b1, next) = <Hex as Parser<I>>::parse(input, next, context)?;
-                let (b2, next) = <Hex as Parser<I>>::parse(input, next, context)?;
-                let (b3, next) = <Hex as Parser<I>>::parse(input, next, context)?;
-                let (b4, next) = <Hex as Parser<I>>::parse(input, next, context)?;
-                let byte = (b1 as u32) << 24 | (b2 as u32) << 16 | (b3 as u32) << 8 | (b4 as u32);
-                let c = byte
-                    .try_into()
-                    .map_err(|_| input.error_at(current, "Escape"))?;
-                Ok((c, next))
+        // The backslash is behind `current`, so errors about the whole escape point
+        // at its `u`.
+        escape_char(input, current, current, context)
+    }
+}
+
+/// Decodes an escape sequence into a char, as `Escape` does.
+fn escape_char<I: Input>(
+    input: &I,
+    backslash: I::Position,
+    current: I::Position,
+    context: &ParserContext,
+) -> ResultOf<I, char> {
+    match decode_escape(input, backslash, current, context.options()) {
+        Ok((Escaped::Char(c), next)) => Ok((c, next)),
+        Ok((Escaped::Surrogate(_), next)) => Ok((char::REPLACEMENT_CHARACTER, next)),
+        Err(failure) => Err(input.error_at(failure.position, failure.reason)),
+    }
+}
+
+/// Parses the four hex digits of a `\u` escape.
+fn hex4<I: Input>(
+    input: &I,
+    current: I::Position,
+) -> Result<(u32, I::Position), Failure<I::Position>> {
+    let mut code = 0;
+    let mut next = current;
+    for _ in 0..4 {
+        match input.next(next) {
+            Ok((c, after)) if c.is_ascii_hexdigit() => {
+                code = code << 4 | c.to_digit(16).unwrap();
+                next = after;
+            }
+            _ => {
+                return Err(Failure::new(next, "Hex", vec!["hex digit"])
+                    .with_kind(JsonErrorKind::InvalidEscape))
             }
-            _ => Err(input.error_at(current, "Escape")),
         }
     }
+    Ok((code, next))
+}
+
+/// Parses a `\u` escape of a low surrogate following a high one, if there is one.
+fn low_surrogate<I: Input>(input: &I, current: I::Position) -> Option<(u32, I::Position)> {
+    let (_, next) = input.next(current).ok().filter(|(c, _)| *c == '\\')?;
+    let (_, next) = input.next(next).ok().filter(|(c, _)| *c == 'u')?;
+    hex4(input, next)
+        .ok()
+        .filter(|(code, _)| (0xDC00..=0xDFFF).contains(code))
 }
 
 pub struct Character;
@@ -164,7 +351,7 @@ impl<I: Input> Parser<I> for Character {
             .next(current)
             .map_err(|e| e.add_reason(current, "Character"))?;
         match c {
-            '\\' => <Escape as Parser<I>>::parse(input, next, context),
+            '\\' => escape_char(input, current, next, context),
             '"' => Err(input.error_at(current, "Character")),
             _ => Ok((c, next)),
         }
@@ -173,262 +360,284 @@ impl<I: Input> Parser<I> for Character {
 
 pub type Characters = ZeroOrMore<Character>;
 
-pub struct Member;
+pub struct String;
 
-impl<I: Input> Parser<I> for Member {
-    type Output = (Vec<char>, JsonValue);
+impl<I: Input> Parser<I> for String {
+    type Output = StdString;
     fn parse(
         input: &I,
         current: I::Position,
         context: &ParserContext,
     ) -> ResultOf<I, Self::Output> {
-        let (_, next) = <Whitespace as Parser<I>>::parse(input, current, context)?;
-        let (key, next) = <String as Parser<I>>::parse(input, next, context)?;
-        let (_, next) = <Whitespace as Parser<I>>::parse(input, next, context)?;
-        let next = input
-            .next(next)
-            .and_then(|(c, next)| {
-                if c == ':' {
-                    Ok(next)
-                } else {
-                    Err(input.error_at(next, "Character"))
-                }
-            })
-            .map_err(|e| e.add_reason(current, "Member"))?;
-        let (value, next) = <Element as Parser<I>>::parse(input, next, context)?;
-        Ok(((key, value), next))
+        let (_, mut next) = <DoubleQuoteChar as Parser<I>>::parse(input, current, context)
+            .map_err(|e| e.add_reason(current, "String"))?;
+        // Characters are decoded straight into the output, without collecting them first.
+        let mut output = StdString::new();
+        loop {
+            if let Ok(('"', after)) = input.next(next) {
+                return Ok((output, after));
+            }
+            let (c, after) = <Character as Parser<I>>::parse(input, next, context)
+                .map_err(|e| e.add_reason(current, "String"))?;
+            output.push(c);
+            next = after;
+        }
     }
 }
 
-pub struct Element;
-
-impl<I: Input> Parser<I> for Element {
-    type Output = JsonValue;
-    fn parse(
-        input: &I,
-        current: I::Position,
-        context: &ParserContext,
-    ) -> ResultOf<I, Self::Output> {
-        let (_, next) = <Whitespace as Parser<I>>::parse(input, current, context)?;
-        let (output, next) = <Value as Parser<I>>::parse(input, next, context)?;
-        let (_, next) = <Whitespace as Parser<I>>::parse(input, next, context)?;
-        Ok((output, next))
-    }
+/// A value that can be assembled from the tokens read by `ReaderState`.
+pub(crate) trait FromTokens<'a>: Sized {
+    type Key;
+    fn key(key: Cow<'a, str>) -> Self::Key;
+    fn scalar(scalar: Scalar<'a>) -> Self;
+    fn object(members: Vec<(Self::Key, Self)>) -> Self;
+    fn array(elements: Vec<Self>) -> Self;
 }
 
-pub struct Value;
+impl<'a, B: ObjectBackend> FromTokens<'a> for GenericJsonValue<B> {
+    type Key = StdString;
 
-impl<I: Input> Parser<I> for Value
-where
-    I::Position: Copy,
-{
-    type Output = JsonValue;
-    fn parse(
-        input: &I,
-        current: I::Position,
-        context: &ParserContext,
-    ) -> ResultOf<I, Self::Output> {
-        if let Ok((output, next)) = <Object as Parser<I>>::parse(input, current, context) {
-            return Ok((JsonValue::Object(output), next));
-        }
-        if let Ok((output, next)) = <Array as Parser<I>>::parse(input, current, context) {
-            return Ok((JsonValue::Array(output), next));
-        }
-        if let Ok((output, next)) = <String as Parser<I>>::parse(input, current, context) {
-            return Ok((JsonValue::String(output), next));
-        }
-        if let Ok((output, next)) = <Number as Parser<I>>::parse(input, current, context) {
-            return Ok((JsonValue::Number(output), next));
-        }
-        let (value, next) = input.next_range(current, 4)?;
-        if value == "null" {
-            return Ok((JsonValue::Null, next));
-        }
-        if value == "true" {
-            return Ok((JsonValue::Boolean(true), next));
-        }
-        let (value, next) = input.next_range(current, 5)?;
-        if value == "false" {
-            return Ok((JsonValue::Boolean(false), next));
+    fn key(key: Cow<'a, str>) -> Self::Key {
+        key.into_owned()
+    }
+
+    fn scalar(scalar: Scalar<'a>) -> Self {
+        match scalar {
+            Scalar::String(s) => GenericJsonValue::String(s.into_str().into_owned()),
+            Scalar::Number(n) => GenericJsonValue::Number(n),
+            Scalar::Boolean(b) => GenericJsonValue::Boolean(b),
+            Scalar::Null => GenericJsonValue::Null,
         }
-        Err(input.error_at(current, "Value"))
     }
-}
 
-pub struct Object;
+    fn object(members: Vec<(Self::Key, Self)>) -> Self {
+        GenericJsonValue::Object(B::Object::from_members(members))
+    }
 
-impl<I: Input> Parser<I> for Object {
-    type Output = JsonObject;
-    fn parse(
-        input: &I,
-        current: I::Position,
-        context: &ParserContext,
-    ) -> ResultOf<I, Self::Output> {
-        let context = &context.nest(input, current)?;
-        let (_, next) = <OpenCurlyBracketChar as Parser<I>>::parse(input, current, context)?;
-        let (output, next) =
-            <OneOf<Members, Whitespace> as Parser<I>>::parse(input, next, context)?;
-        let (_, next) = <CloseCurlyBracketChar as Parser<I>>::parse(input, next, context)?;
-        let output = match output {
-            Either::A(a) => a,
-            Either::B(_) => Vec::new(),
-        };
-        Ok((output, next))
+    fn array(elements: Vec<Self>) -> Self {
+        GenericJsonValue::Array(elements)
     }
 }
 
-pub struct Members;
+impl<'a> FromTokens<'a> for JsonValueRef<'a> {
+    type Key = Cow<'a, str>;
 
-impl<I: Input> Parser<I> for Members {
-    type Output = Vec<(Vec<char>, JsonValue)>;
-    fn parse(
-        input: &I,
-        current: I::Position,
-        context: &ParserContext,
-    ) -> ResultOf<I, Self::Output> {
-        let (output, next) = <Member as Parser<I>>::parse(input, current, context)?;
-        let (rest, next) =
-            <ZeroOrMore<Concat<CommaChar, Member>> as Parser<I>>::parse(input, next, context)?;
-        let mut result = Vec::new();
-        result.push(output);
-        if let Either::A(rest) = rest {
-            result.extend(rest.into_iter().map(|(_, m)| m))
+    fn key(key: Cow<'a, str>) -> Self::Key {
+        key
+    }
+
+    fn scalar(scalar: Scalar<'a>) -> Self {
+        match scalar {
+            Scalar::String(s) => JsonValueRef::String(s.into_str()),
+            Scalar::Number(n) => JsonValueRef::Number(n),
+            Scalar::Boolean(b) => JsonValueRef::Boolean(b),
+            Scalar::Null => JsonValueRef::Null,
         }
-        Ok((result, next))
     }
-}
 
-pub struct Elements;
+    fn object(members: Vec<(Self::Key, Self)>) -> Self {
+        JsonValueRef::Object(members)
+    }
 
-impl<I: Input> Parser<I> for Elements {
-    type Output = Vec<JsonValue>;
-    fn parse(
-        input: &I,
-        current: I::Position,
-        context: &ParserContext,
-    ) -> ResultOf<I, Self::Output> {
-        let (output, next) = <Element as Parser<I>>::parse(input, current, context)?;
-        let (rest, next) =
-            <ZeroOrMore<Concat<CommaChar, Element>> as Parser<I>>::parse(input, next, context)?;
-        let mut result = Vec::new();
-        result.push(output);
-        if let Either::A(rest) = rest {
-            result.extend(rest.into_iter().map(|(_, m)| m))
-        }
-        Ok((result, next))
+    fn array(elements: Vec<Self>) -> Self {
+        JsonValueRef::Array(elements)
     }
 }
 
-pub struct Array;
+enum Frame<K, V> {
+    Object(Vec<(K, V)>, Option<K>),
+    Array(Vec<V>),
+}
 
-impl<I: Input> Parser<I> for Array {
-    type Output = Vec<JsonValue>;
-    fn parse(
-        input: &I,
-        current: I::Position,
-        context: &ParserContext,
-    ) -> ResultOf<I, Self::Output> {
-        let context = &context.nest(input, current)?;
-        let (_, next) = <OpenSquareBracketChar as Parser<I>>::parse(input, current, context)?;
-        let (output, next) =
-            <OneOf<Elements, Whitespace> as Parser<I>>::parse(input, next, context)?;
-        let (_, next) = <CloseSquareBracketChar as Parser<I>>::parse(input, next, context)?;
-        let output = match output {
-            Either::A(a) => a,
-            Either::B(_) => Vec::new(),
+/// Assembles the tokens of a document into a value.
+pub(crate) struct TreeBuilder<K, V> {
+    stack: Vec<Frame<K, V>>,
+}
+
+impl<K, V> TreeBuilder<K, V> {
+    pub(crate) fn new() -> Self {
+        Self { stack: Vec::new() }
+    }
+
+    /// Adds the next token, returning the root value once it is complete.
+    pub(crate) fn push<'a>(&mut self, token: Token<'a>) -> Option<V>
+    where
+        V: FromTokens<'a, Key = K>,
+    {
+        let value = match token {
+            Token::BeginObject => {
+                self.stack.push(Frame::Object(Vec::new(), None));
+                return None;
+            }
+            Token::BeginArray => {
+                self.stack.push(Frame::Array(Vec::new()));
+                return None;
+            }
+            Token::Key(key) => {
+                if let Some(Frame::Object(_, pending)) = self.stack.last_mut() {
+                    *pending = Some(V::key(key.into_str()));
+                }
+                return None;
+            }
+            Token::Value(scalar) => V::scalar(scalar),
+            Token::EndObject | Token::EndArray => match self.stack.pop()? {
+                Frame::Object(members, _) => V::object(members),
+                Frame::Array(elements) => V::array(elements),
+            },
         };
-        Ok((output, next))
+        match self.stack.last_mut() {
+            Some(Frame::Object(members, key)) => {
+                members.extend(key.take().map(|key| (key, value)));
+                None
+            }
+            Some(Frame::Array(elements)) => {
+                elements.push(value);
+                None
+            }
+            None => Some(value),
+        }
     }
 }
 
-pub struct Json;
-
-impl<I: Input> Parser<I> for Json {
-    type Output = <Element as Parser<I>>::Output;
-    fn parse(
-        input: &I,
-        current: I::Position,
-        context: &ParserContext,
-    ) -> ResultOf<I, Self::Output> {
-        let (_, next) = <Whitespace as Parser<I>>::parse(input, current, context)?;
-        let (res, next) = <Element as Parser<I>>::parse(input, next, context)?;
-        let (_, next) = <Whitespace as Parser<I>>::parse(input, next, context)?;
-        if input.is_end(next) {
-            Ok((res, next))
-        } else {
-            Err(input.error_at(next, "Expect end of input"))
+/// Parses `input` into any value that can be built from tokens.
+fn parse_tokens<'a, V: FromTokens<'a>>(
+    input: &'a str,
+    options: ParserOptions,
+) -> Result<V, JsonError> {
+    let input = input.as_bytes();
+    let mut state = ReaderState::new(options);
+    let mut builder = TreeBuilder::new();
+    let mut value = None;
+    while let Some(token) = state.next_token(&input, false)? {
+        if let Some(root) = builder.push(token) {
+            value = Some(root);
         }
     }
+    Ok(value.expect("the reader only finishes after a complete value"))
 }
 
-pub fn parse_json(input: &str) -> Result<JsonValue, SimpleError> {
+pub fn parse_json(input: &str) -> Result<JsonValue, JsonError> {
     parse_json_with_options(input, Default::default())
 }
 
 pub fn parse_json_with_options(
     input: &str,
     options: ParserOptions,
-) -> Result<JsonValue, SimpleError> {
-    Json::parse(&input, Default::default(), &ParserContext::new(options)).map(|(ret, _)| ret)
+) -> Result<JsonValue, JsonError> {
+    parse_tokens(input, options)
+}
+
+/// Parses `input` into a value whose objects store their members with backend `B`,
+/// such as `BTreeObjects` with the `object_btree` feature for a `BTreeJsonValue`.
+pub fn parse_json_as<B: ObjectBackend>(
+    input: &str,
+    options: ParserOptions,
+) -> Result<GenericJsonValue<B>, JsonError> {
+    parse_tokens(input, options)
+}
+
+/// Parses `input` and reports each value to `handler` as it is encountered, without
+/// building a `JsonValue` tree.
+pub fn parse_json_with_handler<H: JsonHandler>(
+    input: &str,
+    handler: &mut H,
+) -> Result<(), JsonError> {
+    parse_json_with_handler_and_options(input, handler, Default::default())
+}
+
+pub fn parse_json_with_handler_and_options<H: JsonHandler>(
+    input: &str,
+    handler: &mut H,
+    options: ParserOptions,
+) -> Result<(), JsonError> {
+    let input = input.as_bytes();
+    let mut state = ReaderState::new(options);
+    while let Some(token) = state.next_token(&input, false)? {
+        let flow = match token {
+            Token::BeginObject => handler.start_object(),
+            Token::EndObject => handler.end_object(),
+            Token::BeginArray => handler.start_array(),
+            Token::EndArray => handler.end_array(),
+            Token::Key(Text::Str(key)) => handler.key(&key),
+            Token::Key(Text::Wtf8(key)) => handler.wtf8_key(&key),
+            Token::Value(Scalar::String(Text::Str(value))) => handler.string(&value),
+            Token::Value(Scalar::String(Text::Wtf8(value))) => handler.wtf8_string(&value),
+            Token::Value(Scalar::Number(value)) => handler.number(value),
+            Token::Value(Scalar::Boolean(value)) => handler.bool(value),
+            Token::Value(Scalar::Null) => handler.null(),
+        };
+        if flow.is_break() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Parses `input` without copying strings that contain no escape sequences.
+pub fn parse_json_borrowed(input: &str) -> Result<JsonValueRef<'_>, JsonError> {
+    parse_json_borrowed_with_options(input, Default::default())
+}
+
+pub fn parse_json_borrowed_with_options(
+    input: &str,
+    options: ParserOptions,
+) -> Result<JsonValueRef<'_>, JsonError> {
+    parse_tokens(input, options)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::NumberValue;
+    use crate::traits::Serialize;
+    use crate::{JsonErrorKind, JsonObject, NumberValue, Wtf8Buf};
+    use core::ops::ControlFlow;
     use lite_parser::impls::SimplePosition;
+    use lite_parser::parser::NumberOverflow;
 
     #[test]
     fn it_works() {
         assert_eq!(
             parse_json(
-                &r#"{ "test": 1, "test2": [1e-4, 2.041e2, true, false, null, "\"1\n\""], "test3": [], "test4": {} }"#
+                r#"{ "test": 1, "test2": [1e-4, 2.041e2, true, false, null, "\"1\n\""], "test3": [], "test4": {} }"#
             ),
-            Ok(JsonValue::Object(vec![
+            Ok(JsonValue::Object(JsonObject::from_members(vec![
                 (
-                    vec!['t', 'e', 's', 't'],
-                    JsonValue::Number(NumberValue {
-                        integer: 1,
-                        fraction: 0,
-                        fraction_length: 0,
-                        exponent: 0,
-                        negative: false,
-                    })
+                    "test".into(),
+                    JsonValue::Number(NumberValue::new(1, 0, 0, 0, false))
                 ),
                 (
-                    vec!['t', 'e', 's', 't', '2'],
+                    "test2".into(),
                     JsonValue::Array(vec![
-                        JsonValue::Number(NumberValue {
-                            integer: 1,
-                            fraction: 0,
-                            fraction_length: 0,
-                            exponent: -4,
-                            negative: false,
-                        }),
-                        JsonValue::Number(NumberValue {
-                            integer: 2,
-                            fraction: 41,
-                            fraction_length: 3,
-                            exponent: 2,
-                            negative: false,
-                        }),
+                        JsonValue::Number(NumberValue::new(1, 0, 0, -4, false)),
+                        JsonValue::Number(NumberValue::new(2, 41, 3, 2, false)),
                         JsonValue::Boolean(true),
                         JsonValue::Boolean(false),
                         JsonValue::Null,
-                        JsonValue::String(vec!['\"', '1', 'n', '\"'])
+                        JsonValue::String("\"1\n\"".into())
                     ])
                 ),
-                (vec!['t', 'e', 's', 't', '3'], JsonValue::Array(vec![])),
-                (vec!['t', 'e', 's', 't', '4'], JsonValue::Object(vec![]))
-            ]))
+                ("test3".into(), JsonValue::Array(vec![])),
+                (
+                    "test4".into(),
+                    JsonValue::Object(JsonObject::from_members(vec![]))
+                )
+            ])))
         )
     }
 
     #[test]
     fn it_should_consume_all() {
         assert_eq!(
-            parse_json(&r#""1"a"#),
-            Err(SimpleError {
+            parse_json(r#""1"a"#),
+            Err(JsonError {
+                kind: JsonErrorKind::TrailingCharacters,
+                position: SimplePosition {
+                    index: 3,
+                    line: 0,
+                    column: 3
+                },
+                found: Some('a'),
+                expected: vec!["end of input"],
                 reasons: vec![(
                     SimplePosition {
                         index: 3,
@@ -445,21 +654,16 @@ mod tests {
     fn it_accepts_nest_level() {
         assert_eq!(
             parse_json_with_options(
-                &r#"{ "test": 1 }"#,
+                r#"{ "test": 1 }"#,
                 ParserOptions {
-                    max_nest_level: Some(1)
+                    max_nest_level: Some(1),
+                    ..Default::default()
                 }
             ),
-            Ok(JsonValue::Object(vec![(
-                vec!['t', 'e', 's', 't'],
-                JsonValue::Number(NumberValue {
-                    integer: 1,
-                    fraction: 0,
-                    fraction_length: 0,
-                    exponent: 0,
-                    negative: false,
-                })
-            ),]))
+            Ok(JsonValue::Object(JsonObject::from_members(vec![(
+                "test".into(),
+                JsonValue::Number(NumberValue::new(1, 0, 0, 0, false))
+            ),])))
         );
     }
 
@@ -467,18 +671,19 @@ mod tests {
     fn it_accepts_more_nest_level() {
         assert_eq!(
             parse_json_with_options(
-                &r#"{ "test": { "a": [ {} ] } }"#,
+                r#"{ "test": { "a": [ {} ] } }"#,
                 ParserOptions {
-                    max_nest_level: Some(5)
+                    max_nest_level: Some(5),
+                    ..Default::default()
                 }
             ),
-            Ok(JsonValue::Object(vec![(
-                vec!['t', 'e', 's', 't'],
-                JsonValue::Object(vec![(
-                    vec!['a'],
-                    JsonValue::Array(vec![JsonValue::Object(vec![])])
-                )])
-            )]))
+            Ok(JsonValue::Object(JsonObject::from_members(vec![(
+                "test".into(),
+                JsonValue::Object(JsonObject::from_members(vec![(
+                    "a".into(),
+                    JsonValue::Array(vec![JsonValue::Object(JsonObject::from_members(vec![]))])
+                )]))
+            )])))
         );
     }
 
@@ -486,57 +691,631 @@ mod tests {
     fn it_error_on_too_deep_nest() {
         assert_eq!(
             parse_json_with_options(
-                &r#"{ "test": { "a": [ {} ] } }"#,
+                r#"{ "test": { "a": [ {} ] } }"#,
                 ParserOptions {
-                    max_nest_level: Some(3)
+                    max_nest_level: Some(3),
+                    ..Default::default()
                 }
+            )
+            .map_err(|e| e.reasons),
+            Err(vec![(
+                SimplePosition {
+                    index: 19,
+                    line: 0,
+                    column: 19
+                },
+                "Exceeded nest level"
+            )])
+        );
+    }
+
+    #[test]
+    fn it_reports_furthest_failure() {
+        assert_eq!(
+            parse_json(r#"[1, {"a": tru}]"#).map_err(|e| e.reasons),
+            Err(vec![(
+                SimplePosition {
+                    index: 13,
+                    line: 0,
+                    column: 13
+                },
+                "ExpectChar"
+            )])
+        );
+        assert_eq!(
+            parse_json_borrowed(r#"{"a": [1, 2], "b": {"c" 1}}"#).map_err(|e| e.reasons),
+            Err(vec![(
+                SimplePosition {
+                    index: 24,
+                    line: 0,
+                    column: 24
+                },
+                "Character"
+            )])
+        );
+    }
+
+    #[test]
+    fn it_reports_where_the_input_goes_wrong() {
+        let error = |input| {
+            let e = parse_json(input).unwrap_err();
+            (
+                e.position.index,
+                e.reasons[0].0.index,
+                e.reasons[0].1,
+                e.expected,
+            )
+        };
+        assert_eq!(
+            error("[1,]"),
+            (3, 3, "Value", crate::json_reader::VALUE.to_vec())
+        );
+        assert_eq!(error("[01]"), (2, 2, "ExpectChar", vec![",", "]"]));
+        assert_eq!(error("[-]"), (2, 2, "Number", vec!["digit"]));
+        assert_eq!(error("[nul]"), (4, 4, "ExpectChar", vec!["l"]));
+        assert_eq!(error("fals"), (4, 4, "ExpectChar", vec!["e"]));
+    }
+
+    #[test]
+    fn handles_decimal_number() {
+        assert_eq!(
+            parse_json(r#"-1.5"#,),
+            Ok(JsonValue::Number(NumberValue::new(1, 5, 1, 0, true)))
+        );
+
+        assert_eq!(
+            parse_json(r#"-0.5"#,),
+            Ok(JsonValue::Number(NumberValue::new(0, 5, 1, 0, true)))
+        );
+
+        assert_eq!(
+            parse_json(r#"0.5"#,),
+            Ok(JsonValue::Number(NumberValue::new(0, 5, 1, 0, false)))
+        );
+    }
+
+    #[test]
+    fn handles_multi_byte_characters() {
+        assert_eq!(
+            parse_json("{ \"ключ\": \"日本語\" }"),
+            Ok(JsonValue::Object(JsonObject::from_members(vec![(
+                "ключ".into(),
+                JsonValue::String("日本語".into())
+            )])))
+        );
+
+        assert_eq!(
+            parse_json("[\"é\",\n\"ü\"] x").map_err(|e| e.reasons),
+            Err(vec![(
+                SimplePosition {
+                    index: 11,
+                    line: 1,
+                    column: 5
+                },
+                "Expect end of input"
+            )])
+        );
+    }
+
+    #[test]
+    fn it_parses_borrowed() {
+        let input = r#"{ "plain": ["text", 1.5, true, null], "esc\"aped": "a\\b" }"#;
+        let value = parse_json_borrowed(input).unwrap();
+        let obj = match &value {
+            JsonValueRef::Object(obj) => obj,
+            _ => panic!("expected object"),
+        };
+        assert!(matches!(obj[0].0, Cow::Borrowed("plain")));
+        assert!(matches!(obj[1].0, Cow::Owned(ref key) if key == "esc\"aped"));
+        assert_eq!(obj[1].1.as_str(), Some("a\\b"));
+        match &obj[0].1 {
+            JsonValueRef::Array(arr) => assert!(matches!(
+                arr[0],
+                JsonValueRef::String(Cow::Borrowed("text"))
+            )),
+            _ => panic!("expected array"),
+        }
+        assert_eq!(value.into_owned(), parse_json(input).unwrap());
+    }
+
+    #[test]
+    fn it_errors_on_borrowed() {
+        assert_eq!(
+            parse_json_borrowed(r#""1"a"#).map_err(|e| e.reasons),
+            Err(vec![(
+                SimplePosition {
+                    index: 3,
+                    line: 0,
+                    column: 3
+                },
+                "Expect end of input"
+            )])
+        );
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<std::string::String>,
+        stop_after: Option<usize>,
+    }
+
+    impl Recorder {
+        fn flow(&self) -> ControlFlow<()> {
+            if Some(self.events.len()) == self.stop_after {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    impl JsonHandler for Recorder {
+        fn start_object(&mut self) -> ControlFlow<()> {
+            self.events.push("{".into());
+            self.flow()
+        }
+        fn key(&mut self, key: &str) -> ControlFlow<()> {
+            self.events.push(format!("key {}", key));
+            self.flow()
+        }
+        fn end_object(&mut self) -> ControlFlow<()> {
+            self.events.push("}".into());
+            self.flow()
+        }
+        fn start_array(&mut self) -> ControlFlow<()> {
+            self.events.push("[".into());
+            self.flow()
+        }
+        fn end_array(&mut self) -> ControlFlow<()> {
+            self.events.push("]".into());
+            self.flow()
+        }
+        fn string(&mut self, value: &str) -> ControlFlow<()> {
+            self.events.push(format!("string {}", value));
+            self.flow()
+        }
+        fn number(&mut self, value: NumberValue) -> ControlFlow<()> {
+            self.events.push(format!("number {}", value.to_f64()));
+            self.flow()
+        }
+        fn bool(&mut self, value: bool) -> ControlFlow<()> {
+            self.events.push(format!("bool {}", value));
+            self.flow()
+        }
+        fn null(&mut self) -> ControlFlow<()> {
+            self.events.push("null".into());
+            self.flow()
+        }
+    }
+
+    #[test]
+    fn it_reports_events_to_handler() {
+        let mut recorder = Recorder::default();
+        assert_eq!(
+            parse_json_with_handler(
+                r#" { "a": [1.5, "x", true, false, null, {}, []], "b": { "c": -2 } } "#,
+                &mut recorder
             ),
-            Err(SimpleError {
-                reasons: vec![(
-                    SimplePosition {
-                        index: 0,
-                        line: 0,
-                        column: 0
-                    },
-                    "Value"
-                )]
+            Ok(())
+        );
+        assert_eq!(
+            recorder.events,
+            vec![
+                "{",
+                "key a",
+                "[",
+                "number 1.5",
+                "string x",
+                "bool true",
+                "bool false",
+                "null",
+                "{",
+                "}",
+                "[",
+                "]",
+                "]",
+                "key b",
+                "{",
+                "key c",
+                "number -2",
+                "}",
+                "}"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_handler_errors() {
+        let mut recorder = Recorder::default();
+        assert_eq!(
+            parse_json_with_handler(r#"[1] 2"#, &mut recorder).map_err(|e| e.reasons),
+            Err(vec![(
+                SimplePosition {
+                    index: 4,
+                    line: 0,
+                    column: 4
+                },
+                "Expect end of input"
+            )])
+        );
+
+        let mut recorder = Recorder::default();
+        assert!(parse_json_with_handler_and_options(
+            r#"[[1]]"#,
+            &mut recorder,
+            ParserOptions {
+                max_nest_level: Some(1),
+                ..Default::default()
+            }
+        )
+        .is_err());
+        assert_eq!(recorder.events, vec!["["]);
+    }
+
+    #[test]
+    fn it_stops_when_the_handler_breaks() {
+        let mut recorder = Recorder {
+            stop_after: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_json_with_handler(r#"{"a": [1, 2, 3], "b": x"#, &mut recorder),
+            Ok(())
+        );
+        assert_eq!(recorder.events, vec!["{", "key a", "["]);
+    }
+
+    #[test]
+    fn it_reports_structured_errors() {
+        let error = parse_json(r#"{ "a": [1, 2 x] }"#).unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::UnexpectedChar);
+        assert_eq!(
+            error.position,
+            SimplePosition {
+                index: 13,
+                line: 0,
+                column: 13
+            }
+        );
+        assert_eq!(error.found, Some('x'));
+        assert_eq!(error.expected, vec![",", "]"]);
+
+        let error = parse_json("{ \"a\":\n").unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::UnexpectedEof);
+        assert_eq!(error.position.line, 1);
+        assert_eq!(error.found, None);
+        assert_eq!(
+            error.expected,
+            vec!["{", "[", "string", "number", "true", "false", "null"]
+        );
+
+        let error = parse_json(r#"["\u12x4"]"#).unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::InvalidEscape);
+        assert_eq!(error.position.index, 6);
+        assert_eq!(error.expected, vec!["hex digit"]);
+
+        let error = parse_json_with_options(
+            r#"{ "test": { "a": [ {} ] } }"#,
+            ParserOptions {
+                max_nest_level: Some(3),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::NestingTooDeep);
+        assert_eq!(error.position.index, 19);
+    }
+
+    #[test]
+    fn it_decodes_unicode_escapes() {
+        let corpus: &[(&str, Option<&str>)] = &[
+            (r#""\u0041""#, Some("A")),
+            (r#""\u00e9\u00E9""#, Some("\u{E9}\u{E9}")),
+            (r#""\u20AC""#, Some("\u{20AC}")),
+            (r#""\u0000""#, Some("\u{0}")),
+            (r#""\uFFFF""#, Some("\u{FFFF}")),
+            (r#""a\u0062c""#, Some("abc")),
+            (r#""\uD83D\uDE00""#, Some("\u{1F600}")),
+            (r#""\ud83d\ude00""#, Some("\u{1F600}")),
+            (r#""\uDBFF\uDFFF""#, Some("\u{10FFFF}")),
+            (r#""\uD800\uDC00x""#, Some("\u{10000}x")),
+            (r#""\uD83D""#, None),
+            (r#""\uDE00""#, None),
+            (r#""\uD83Dx""#, None),
+            (r#""\uD83D\n""#, None),
+            (r#""\uD83D\uD83D""#, None),
+            (r#""\uDE00\uD83D""#, None),
+            (r#""\u12""#, None),
+            (r#""\u12G4""#, None),
+            (r#""\U0041""#, None),
+        ];
+        for (input, expected) in corpus {
+            let expected = expected.map(|s| JsonValue::String(s.into()));
+            assert_eq!(parse_json(input).ok(), expected, "{}", input);
+            assert_eq!(
+                parse_json_borrowed(input).ok().map(JsonValue::from),
+                expected,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn it_handles_lone_surrogates() {
+        // The error points at the backslash of the escape.
+        let error = parse_json(r#"["\uD83Dx"]"#).unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::InvalidEscape);
+        assert_eq!(error.position.index, 2);
+        assert_eq!(error.expected, vec!["surrogate pair"]);
+
+        let options = ParserOptions {
+            lone_surrogate: LoneSurrogate::Replace,
+            ..Default::default()
+        };
+        let corpus = [
+            (r#""\uD83D""#, "\u{FFFD}"),
+            (r#""\uDE00""#, "\u{FFFD}"),
+            (r#""\uD83Dx""#, "\u{FFFD}x"),
+            (r#""\uD83D\uD83D\uDE00""#, "\u{FFFD}\u{1F600}"),
+            (r#""\uDE00\uD83D""#, "\u{FFFD}\u{FFFD}"),
+            (r#""\uD83D\uDE00""#, "\u{1F600}"),
+        ];
+        for (input, expected) in corpus.iter() {
+            assert_eq!(
+                parse_json_with_options(input, options.clone()),
+                Ok(JsonValue::String((*expected).into())),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[derive(Default)]
+    struct Wtf8Recorder {
+        keys: Vec<Wtf8Buf>,
+        strings: Vec<Wtf8Buf>,
+    }
+
+    impl JsonHandler for Wtf8Recorder {
+        fn key(&mut self, key: &str) -> ControlFlow<()> {
+            self.keys.push(key.into());
+            ControlFlow::Continue(())
+        }
+        fn wtf8_key(&mut self, key: &Wtf8Buf) -> ControlFlow<()> {
+            self.keys.push(key.clone());
+            ControlFlow::Continue(())
+        }
+        fn string(&mut self, value: &str) -> ControlFlow<()> {
+            self.strings.push(value.into());
+            ControlFlow::Continue(())
+        }
+        fn wtf8_string(&mut self, value: &Wtf8Buf) -> ControlFlow<()> {
+            self.strings.push(value.clone());
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn it_preserves_lone_surrogates() {
+        let options = ParserOptions {
+            lone_surrogate: LoneSurrogate::Preserve,
+            ..Default::default()
+        };
+        let input = r#"{"k\uDE00": ["a\uD83D\u00e9\uD83D\uDE00", "plain\n"]}"#;
+        let mut recorder = Wtf8Recorder::default();
+        parse_json_with_handler_and_options(input, &mut recorder, options.clone()).unwrap();
+        assert_eq!(recorder.keys[0].as_bytes(), b"k\xED\xB8\x80");
+        assert_eq!(
+            recorder.strings[0].as_bytes(),
+            b"a\xED\xA0\xBD\xC3\xA9\xF0\x9F\x98\x80".as_ref()
+        );
+        assert_eq!(recorder.strings[1].as_str(), Some("plain\n"));
+        // Serializing writes the surrogates back as escapes.
+        assert_eq!(
+            recorder.strings[0].serialize(),
+            r#""a\ud83dé😀""#.as_bytes()
+        );
+        assert_eq!(recorder.keys[0].serialize(), br#""k\ude00""#);
+
+        // Outputs of `str` replace them.
+        assert_eq!(
+            parse_json_with_options(input, options).unwrap()["k\u{FFFD}"][0],
+            JsonValue::String("a\u{FFFD}é😀".into())
+        );
+        let mut recorder = Recorder::default();
+        parse_json_with_handler_and_options(
+            r#"["\uDE00"]"#,
+            &mut recorder,
+            ParserOptions {
+                lone_surrogate: LoneSurrogate::Preserve,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(recorder.events, vec!["[", "string \u{FFFD}", "]"]);
+    }
+
+    #[test]
+    fn it_detects_number_overflow() {
+        assert_eq!(
+            parse_json("18446744073709551615"),
+            Ok(JsonValue::Number(NumberValue::new(
+                u64::MAX,
+                0,
+                0,
+                0,
+                false
+            )))
+        );
+        for (input, index) in [
+            ("18446744073709551616", 0),
+            ("[1, -99999999999999999999]", 4),
+            ("1e2147483648", 0),
+            ("[0.5e-99999999999]", 1),
+        ]
+        .iter()
+        {
+            // The overflow policy is ignored when the exact text is kept.
+            if cfg!(feature = "arbitrary_precision") {
+                assert!(parse_json(input).is_ok(), "{}", input);
+                assert!(parse_json_borrowed(input).is_ok(), "{}", input);
+                assert!(crate::JsonReader::new(input).all(|token| token.is_ok()));
+                continue;
+            }
+            let error = parse_json(input).unwrap_err();
+            assert_eq!(error.kind, JsonErrorKind::NumberOverflow, "{}", input);
+            assert_eq!(error.position.index, *index, "{}", input);
+            assert_eq!(error.expected, Vec::<&str>::new(), "{}", input);
+            assert_eq!(
+                parse_json_borrowed(input).unwrap_err().kind,
+                JsonErrorKind::NumberOverflow
+            );
+            let reader_error = crate::JsonReader::new(input)
+                .find_map(|token| token.err())
+                .unwrap();
+            assert_eq!(reader_error.kind, JsonErrorKind::NumberOverflow);
+            assert_eq!(reader_error.position.index, *index);
+        }
+    }
+
+    #[test]
+    fn it_keeps_overflowing_fractions() {
+        let input = r#"{"a":0.123456789012345678901}"#;
+        let value = parse_json(input).unwrap();
+        assert_eq!(value.serialize(), input.as_bytes());
+        let number = value["a"].as_number().unwrap();
+        assert_eq!(number.lexical(), Some("0.123456789012345678901"));
+        assert_eq!(number.to_decimal(), Some((123456789012345678901, 21)));
+        assert_eq!(
+            parse_json_borrowed(input).unwrap().into_owned().serialize(),
+            input.as_bytes()
+        );
+        let read: Vec<_> = crate::JsonReader::new(input)
+            .filter_map(|token| match token.unwrap() {
+                crate::JsonToken::Value(JsonValue::Number(n)) => Some(n),
+                _ => None,
             })
+            .collect();
+        assert_eq!(read[0].lexical(), Some("0.123456789012345678901"));
+
+        // Numbers that fit keep no text unless every number does.
+        let number = parse_json("0.12345678901234567890").unwrap();
+        assert_eq!(
+            number.as_number().unwrap().lexical().is_some(),
+            cfg!(feature = "arbitrary_precision")
         );
     }
 
     #[test]
-    fn handles_decimal_number() {
+    fn it_keeps_overflowing_numbers_exactly() {
+        let options = ParserOptions {
+            number_overflow: NumberOverflow::KeepText,
+            ..Default::default()
+        };
+        let number = |input| match parse_json_with_options(input, options.clone()) {
+            Ok(JsonValue::Number(n)) => n,
+            other => panic!("{:?}", other),
+        };
+        for input in [
+            "-123456789012345678901234.5e-2",
+            "1e99999999999",
+            "1e-99999999999",
+            "18446744073709551616",
+            "1e2147483648",
+        ]
+        .iter()
+        {
+            let number = number(input);
+            assert_eq!(number.lexical(), Some(*input));
+            assert_eq!(number.serialize(), input.as_bytes());
+        }
+        assert_eq!(
+            number("-123456789012345678901234.5e-2").to_decimal(),
+            Some((-1234567890123456789012345, 3))
+        );
         assert_eq!(
-            parse_json(&r#"-1.5"#,),
-            Ok(JsonValue::Number(NumberValue {
-                integer: 1,
-                fraction: 5,
-                fraction_length: 1,
-                exponent: 0,
-                negative: true,
-            }))
+            number("18446744073709551616").to_u128(),
+            Some(18446744073709551616)
         );
+        assert_eq!(number("1e99999999999").exponent(), i32::MAX);
+        assert_eq!(number("1e-99999999999").exponent(), i32::MIN);
+        assert_eq!(number("1e-99999999999").to_decimal(), None);
 
+        // Numbers that fit keep no text unless every number does.
         assert_eq!(
-            parse_json(&r#"-0.5"#,),
-            Ok(JsonValue::Number(NumberValue {
-                integer: 0,
-                fraction: 5,
-                fraction_length: 1,
-                exponent: 0,
-                negative: true,
-            }))
+            number("123").lexical().is_some(),
+            cfg!(feature = "arbitrary_precision")
         );
+    }
+}
 
+#[cfg(all(test, feature = "arbitrary_precision"))]
+mod arbitrary_precision_tests {
+    use super::*;
+    use crate::traits::Serialize;
+    use crate::JsonReader;
+
+    #[test]
+    fn it_keeps_lexical_digits() {
+        let input = r#"[340282366920938463463374607431768211455,-170141183460469231731687303715884105728,0.000000000000000000000000000001,1.50E+2,-0e-0,123456789012345678901234567890.1234567890]"#;
+        let value = parse_json(input).unwrap();
+        assert_eq!(value.serialize(), input.as_bytes());
+        match parse_json_borrowed("[1.50E+2]").unwrap() {
+            JsonValueRef::Array(values) => match &values[0] {
+                JsonValueRef::Number(n) => assert_eq!(n.lexical(), Some("1.50E+2")),
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        }
+
+        let numbers = value.as_array().unwrap();
+        let lexical: Vec<_> = numbers
+            .iter()
+            .map(|n| n.as_number().unwrap().lexical().unwrap())
+            .collect();
         assert_eq!(
-            parse_json(&r#"0.5"#,),
-            Ok(JsonValue::Number(NumberValue {
-                integer: 0,
-                fraction: 5,
-                fraction_length: 1,
-                exponent: 0,
-                negative: false,
-            }))
+            lexical,
+            [
+                "340282366920938463463374607431768211455",
+                "-170141183460469231731687303715884105728",
+                "0.000000000000000000000000000001",
+                "1.50E+2",
+                "-0e-0",
+                "123456789012345678901234567890.1234567890",
+            ]
         );
+
+        let number = |i: usize| numbers[i].as_number().unwrap();
+        assert_eq!(number(0).to_u128(), Some(u128::MAX));
+        assert_eq!(number(0).to_i128(), None);
+        assert_eq!(number(1).to_i128(), Some(i128::MIN));
+        assert_eq!(number(1).to_u128(), None);
+        assert_eq!(number(2).to_u128(), None);
+        assert_eq!(number(2).to_decimal(), Some((1, 30)));
+        assert_eq!(number(3).to_u64(), Some(150));
+        assert_eq!(number(3).to_decimal(), Some((150, 0)));
+        assert_eq!(number(4).to_i8(), Some(0));
+        assert_eq!(
+            number(5).to_decimal(),
+            Some((123456789012345678901234567890123456789, 9))
+        );
+
+        let read: Vec<_> = JsonReader::new(input)
+            .filter_map(|token| match token.unwrap() {
+                crate::JsonToken::Value(JsonValue::Number(n)) => n.lexical().map(StdString::from),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(read, lexical);
+    }
+
+    #[test]
+    fn it_ignores_overflow_policy() {
+        let value = parse_json("1e99999999999").unwrap();
+        let number = value.as_number().unwrap();
+        assert_eq!(number.exponent(), i32::MAX);
+        assert_eq!(number.lexical(), Some("1e99999999999"));
+        assert_eq!(number.to_u128(), None);
     }
 }
@@ -0,0 +1,657 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{borrow::Cow, string::String as StdString, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::{borrow::Cow, string::String as StdString};
+
+use crate::json::{JsonValue, NumberValue};
+use crate::json_error::{Failure, JsonError, JsonErrorKind};
+use crate::json_parser::{decode_escape, number_overflow, number_value, Escaped, FromTokens};
+use crate::wtf8::Wtf8Buf;
+use lite_parser::{
+    impls::BytePosition,
+    parser::{ParserContext, ParserOptions},
+    traits::Input,
+};
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq)]
+pub enum JsonToken {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Key(StdString),
+    /// A string, number, boolean or null value.
+    Value(JsonValue),
+}
+
+impl<'a> From<Token<'a>> for JsonToken {
+    fn from(token: Token<'a>) -> Self {
+        match token {
+            Token::BeginObject => JsonToken::BeginObject,
+            Token::EndObject => JsonToken::EndObject,
+            Token::BeginArray => JsonToken::BeginArray,
+            Token::EndArray => JsonToken::EndArray,
+            Token::Key(key) => JsonToken::Key(key.into_str().into_owned()),
+            Token::Value(scalar) => JsonToken::Value(JsonValue::scalar(scalar)),
+        }
+    }
+}
+
+/// A token read by `ReaderState`. Strings borrow from the input unless they
+/// contain escape sequences.
+pub(crate) enum Token<'a> {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Key(Text<'a>),
+    Value(Scalar<'a>),
+}
+
+/// A decoded string. It only holds WTF-8 if `LoneSurrogate::Preserve` kept a lone
+/// surrogate in it.
+pub(crate) enum Text<'a> {
+    Str(Cow<'a, str>),
+    Wtf8(Wtf8Buf),
+}
+
+impl<'a> Text<'a> {
+    /// Returns the string, with any lone surrogates replaced by U+FFFD.
+    pub(crate) fn into_str(self) -> Cow<'a, str> {
+        match self {
+            Text::Str(s) => s,
+            Text::Wtf8(s) => Cow::Owned(s.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+pub(crate) enum Scalar<'a> {
+    String(Text<'a>),
+    Number(NumberValue),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Value,
+    FirstMember,
+    Member,
+    Colon,
+    FirstElement,
+    Separator,
+    End,
+    Finished,
+}
+
+/// The tokenizer behind every parser in this crate, kept apart from the input it
+/// reads so that the input can grow, as it does for `JsonStreamParser`.
+#[derive(Clone)]
+pub(crate) struct ReaderState {
+    pub(crate) position: BytePosition,
+    expect: Expect,
+    stack: Vec<(Container, ParserContext)>,
+    root: ParserContext,
+}
+
+pub(crate) const VALUE: &[&str] = &["{", "[", "string", "number", "true", "false", "null"];
+
+fn skip_whitespace(input: &&[u8], current: BytePosition) -> BytePosition {
+    let mut pos = current;
+    while let Ok((' ' | '\r' | '\n' | '\t', next)) = input.next(pos) {
+        pos = next;
+    }
+    pos
+}
+
+fn skip_digits(input: &&[u8], current: BytePosition) -> BytePosition {
+    let mut pos = current;
+    while let Ok(('0'..='9', next)) = input.next(pos) {
+        pos = next;
+    }
+    pos
+}
+
+/// Reads the string whose opening quote is at `current`, borrowing it from the
+/// input unless it contains escape sequences. When `skip` is set, the string is
+/// only validated and an empty string is returned.
+fn read_string<'a>(
+    input: &&'a [u8],
+    current: BytePosition,
+    options: &ParserOptions,
+    skip: bool,
+) -> Result<(Text<'a>, BytePosition), Failure> {
+    let bytes: &'a [u8] = input;
+    // `next` has already validated the bytes between two of its positions.
+    let slice = |from: BytePosition, to: BytePosition| {
+        core::str::from_utf8(&bytes[from.offset as usize..to.offset as usize]).unwrap_or_default()
+    };
+    let (_, start) = input
+        .next(current)
+        .map_err(|_| Failure::new(current, "String", vec!["\""]))?;
+    let mut decoded: Option<StdString> = None;
+    // Takes over from `decoded` once a lone surrogate is kept.
+    let mut wtf8: Option<Wtf8Buf> = None;
+    // The start of the characters not yet copied into the output.
+    let mut run = start;
+    let mut pos = start;
+    loop {
+        let (c, next) = input
+            .next(pos)
+            .map_err(|_| Failure::new(pos, "String", vec!["\""]))?;
+        match c {
+            '"' => {
+                let output = match (decoded, wtf8) {
+                    _ if skip => Text::Str(Cow::Borrowed("")),
+                    (_, Some(mut wtf8)) => {
+                        wtf8.push_str(slice(run, pos));
+                        Text::Wtf8(wtf8)
+                    }
+                    (Some(mut decoded), None) => {
+                        decoded.push_str(slice(run, pos));
+                        Text::Str(Cow::Owned(decoded))
+                    }
+                    (None, None) => Text::Str(Cow::Borrowed(slice(run, pos))),
+                };
+                return Ok((output, next));
+            }
+            '\\' => {
+                let (escaped, after) = decode_escape(input, pos, next, options)?;
+                if !skip {
+                    let prefix = slice(run, pos);
+                    match (escaped, wtf8.as_mut()) {
+                        (Escaped::Char(c), Some(wtf8)) => {
+                            wtf8.push_str(prefix);
+                            wtf8.push(c);
+                        }
+                        (Escaped::Char(c), None) => {
+                            let decoded = decoded.get_or_insert_with(StdString::new);
+                            decoded.push_str(prefix);
+                            decoded.push(c);
+                        }
+                        (Escaped::Surrogate(code), _) => {
+                            let wtf8 = wtf8
+                                .get_or_insert_with(|| decoded.take().unwrap_or_default().into());
+                            wtf8.push_str(prefix);
+                            wtf8.push_surrogate(code);
+                        }
+                    }
+                }
+                run = after;
+                pos = after;
+            }
+            _ => pos = next,
+        }
+    }
+}
+
+/// Reads `word` starting at `current`, failing at the first byte that differs.
+fn read_literal(
+    input: &&[u8],
+    current: BytePosition,
+    word: &'static str,
+) -> Result<BytePosition, Failure> {
+    let mut pos = current;
+    for (i, b) in word.bytes().enumerate() {
+        match input.next(pos) {
+            Ok((c, next)) if c == b as char => pos = next,
+            _ => return Err(Failure::new(pos, "ExpectChar", vec![&word[i..=i]])),
+        }
+    }
+    Ok(pos)
+}
+
+/// Skips the number starting at `current`, returning the position after it.
+fn skip_number(input: &&[u8], current: BytePosition) -> Result<BytePosition, Failure> {
+    let digit = |pos| match input.next(pos) {
+        Ok(('0'..='9', next)) => Ok(next),
+        _ => Err(Failure::new(pos, "Number", vec!["digit"])),
+    };
+    let mut pos = current;
+    if let Ok(('-', next)) = input.next(pos) {
+        pos = next;
+    }
+    pos = match input.next(pos) {
+        Ok(('1'..='9', next)) => skip_digits(input, next),
+        _ => digit(pos)?,
+    };
+    if let Ok(('.', next)) = input.next(pos) {
+        pos = skip_digits(input, digit(next)?);
+    }
+    if let Ok(('e' | 'E', next)) = input.next(pos) {
+        let next = match input.next(next) {
+            Ok(('+' | '-', after)) => after,
+            _ => next,
+        };
+        pos = skip_digits(input, digit(next)?);
+    }
+    Ok(pos)
+}
+
+impl ReaderState {
+    pub(crate) fn new(options: ParserOptions) -> Self {
+        Self {
+            position: Default::default(),
+            expect: Expect::Value,
+            stack: Vec::new(),
+            root: ParserContext::new(options),
+        }
+    }
+
+    pub(crate) fn fail(&mut self) {
+        self.expect = Expect::Finished;
+    }
+
+    fn context(&self) -> &ParserContext {
+        self.stack
+            .last()
+            .map(|(_, context)| context)
+            .unwrap_or(&self.root)
+    }
+
+    fn after_value(&mut self, next: BytePosition) {
+        self.position = next;
+        self.expect = if self.stack.is_empty() {
+            Expect::End
+        } else {
+            Expect::Separator
+        };
+    }
+
+    fn end_container<'a>(&mut self, next: BytePosition) -> Token<'a> {
+        let (container, _) = self.stack.pop().expect("only called inside a container");
+        self.after_value(next);
+        match container {
+            Container::Object => Token::EndObject,
+            Container::Array => Token::EndArray,
+        }
+    }
+
+    /// The tokens accepted where the next token starts.
+    fn expected(&self) -> Vec<&'static str> {
+        let closing = match self.stack.last() {
+            Some((Container::Object, _)) => "}",
+            _ => "]",
+        };
+        match self.expect {
+            Expect::Value => VALUE.to_vec(),
+            Expect::FirstMember => vec!["string", "}"],
+            Expect::Member => vec!["string"],
+            Expect::Colon => vec![":"],
+            Expect::FirstElement => {
+                let mut expected = VALUE.to_vec();
+                expected.push("]");
+                expected
+            }
+            Expect::Separator => vec![",", closing],
+            Expect::End | Expect::Finished => vec!["end of input"],
+        }
+    }
+
+    /// A token that does not fit the current state starts at `position`.
+    fn unexpected(&self, position: BytePosition, reason: &'static str) -> Failure {
+        Failure::new(position, reason, self.expected())
+    }
+
+    fn begin_container<'a>(
+        &mut self,
+        input: &&'a [u8],
+        current: BytePosition,
+        container: Container,
+    ) -> Result<Token<'a>, Failure> {
+        let context = self.context().nest(input, current).map_err(|_| {
+            Failure::new(current, "Exceeded nest level", Vec::new())
+                .with_kind(JsonErrorKind::NestingTooDeep)
+        })?;
+        let (_, next) = input.next(current).expect("only called at a bracket");
+        let (token, expect) = match container {
+            Container::Object => (Token::BeginObject, Expect::FirstMember),
+            Container::Array => (Token::BeginArray, Expect::FirstElement),
+        };
+        self.stack.push((container, context));
+        self.position = next;
+        self.expect = expect;
+        Ok(token)
+    }
+
+    fn key<'a>(
+        &mut self,
+        input: &&'a [u8],
+        current: BytePosition,
+        skip: bool,
+    ) -> Result<Token<'a>, Failure> {
+        if !matches!(input.next(current), Ok(('"', _))) {
+            return Err(self.unexpected(current, "ExpectChar"));
+        }
+        let (key, next) = read_string(input, current, self.context().options(), skip)?;
+        self.position = next;
+        self.expect = Expect::Colon;
+        Ok(Token::Key(key))
+    }
+
+    fn value<'a>(
+        &mut self,
+        input: &&'a [u8],
+        current: BytePosition,
+        skip: bool,
+    ) -> Result<Token<'a>, Failure> {
+        let options = self.context().options();
+        let (value, next) = match input.next(current) {
+            Ok(('{', _)) => return self.begin_container(input, current, Container::Object),
+            Ok(('[', _)) => return self.begin_container(input, current, Container::Array),
+            Ok(('"', _)) => {
+                let (output, next) = read_string(input, current, options, skip)?;
+                (Scalar::String(output), next)
+            }
+            Ok(('-' | '0'..='9', _)) => {
+                let next = skip_number(input, current)?;
+                if skip {
+                    (Scalar::Null, next)
+                } else {
+                    let text =
+                        core::str::from_utf8(&input[current.offset as usize..next.offset as usize])
+                            .unwrap_or_default();
+                    let output = number_value(text, number_overflow(options)).ok_or_else(|| {
+                        Failure::new(current, "Number overflow", Vec::new())
+                            .with_kind(JsonErrorKind::NumberOverflow)
+                    })?;
+                    (Scalar::Number(output), next)
+                }
+            }
+            Ok(('n', _)) => (Scalar::Null, read_literal(input, current, "null")?),
+            Ok(('t', _)) => (Scalar::Boolean(true), read_literal(input, current, "true")?),
+            Ok(('f', _)) => (
+                Scalar::Boolean(false),
+                read_literal(input, current, "false")?,
+            ),
+            _ => return Err(self.unexpected(current, "Value")),
+        };
+        self.after_value(next);
+        Ok(Token::Value(value))
+    }
+
+    /// Reads the next token, or returns `None` at the end of the document.
+    ///
+    /// When `skip` is set, keys and values are validated but not decoded, and
+    /// are reported as empty keys and `Null` values without allocating.
+    pub(crate) fn next_token<'a>(
+        &mut self,
+        input: &&'a [u8],
+        skip: bool,
+    ) -> Result<Option<Token<'a>>, JsonError> {
+        self.read_token(input, skip)
+            .map_err(|failure| JsonError::new(input, failure))
+    }
+
+    fn read_token<'a>(
+        &mut self,
+        input: &&'a [u8],
+        skip: bool,
+    ) -> Result<Option<Token<'a>>, Failure> {
+        loop {
+            let current = skip_whitespace(input, self.position);
+            let next = input.next(current).ok();
+            let token = match (self.expect, next) {
+                (Expect::Finished, _) => return Ok(None),
+                (Expect::End, None) if input.is_end(current) => {
+                    self.position = current;
+                    self.expect = Expect::Finished;
+                    return Ok(None);
+                }
+                (Expect::End, _) => {
+                    return Err(self
+                        .unexpected(current, "Expect end of input")
+                        .with_kind(JsonErrorKind::TrailingCharacters));
+                }
+                (Expect::FirstMember, Some(('}', next)))
+                | (Expect::FirstElement, Some((']', next))) => self.end_container(next),
+                (Expect::Value, _) | (Expect::FirstElement, _) => {
+                    self.value(input, current, skip)?
+                }
+                (Expect::FirstMember, _) | (Expect::Member, _) => self.key(input, current, skip)?,
+                (Expect::Colon, Some((':', next))) => {
+                    self.position = next;
+                    self.expect = Expect::Value;
+                    continue;
+                }
+                (Expect::Colon, _) => return Err(self.unexpected(current, "Character")),
+                (Expect::Separator, Some((',', next))) => {
+                    self.position = next;
+                    self.expect = match self.stack.last() {
+                        Some((Container::Object, _)) => Expect::Member,
+                        _ => Expect::Value,
+                    };
+                    continue;
+                }
+                (Expect::Separator, Some((c, next))) => match (self.stack.last(), c) {
+                    (Some((Container::Object, _)), '}') | (Some((Container::Array, _)), ']') => {
+                        self.end_container(next)
+                    }
+                    _ => return Err(self.unexpected(current, "ExpectChar")),
+                },
+                (Expect::Separator, None) => return Err(self.unexpected(current, "ExpectChar")),
+            };
+            return Ok(Some(token));
+        }
+    }
+
+    /// Skips the next value, including all nested values of an object or array.
+    /// If a key is next, the key and its value are skipped. Returns false without
+    /// consuming anything if the enclosing object or array, or the document, ends
+    /// instead.
+    pub(crate) fn skip_value(&mut self, input: &&[u8]) -> Result<bool, JsonError> {
+        let current = skip_whitespace(input, self.position);
+        let at_end = match self.expect {
+            Expect::End | Expect::Finished => true,
+            Expect::FirstMember | Expect::FirstElement | Expect::Separator => {
+                matches!(input.next(current), Ok(('}', _)) | Ok((']', _)))
+            }
+            Expect::Value | Expect::Member | Expect::Colon => false,
+        };
+        if at_end {
+            return Ok(false);
+        }
+        let mut depth = 0u32;
+        loop {
+            let start = skip_whitespace(input, self.position);
+            match self.next_token(input, true)? {
+                Some(Token::BeginObject) | Some(Token::BeginArray) => depth += 1,
+                Some(Token::EndObject) | Some(Token::EndArray) if depth > 0 => depth -= 1,
+                Some(Token::Key(_)) => continue,
+                Some(Token::Value(_)) => {}
+                _ => {
+                    let failure = Failure::new(start, "Expect value", VALUE.to_vec());
+                    return Err(JsonError::new(input, failure));
+                }
+            }
+            if depth == 0 {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// Pull parser yielding the tokens of a JSON document one at a time.
+pub struct JsonReader<'a> {
+    input: &'a [u8],
+    state: ReaderState,
+}
+
+impl<'a> JsonReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, Default::default())
+    }
+
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
+        Self {
+            input: input.as_bytes(),
+            state: ReaderState::new(options),
+        }
+    }
+
+    /// Skips over the next value without allocating. If the next token is a key,
+    /// the key and its value are skipped. Returns false, leaving the reader where it
+    /// is, if the next token ends an object or array or the document is finished.
+    pub fn skip_value(&mut self) -> Result<bool, JsonError> {
+        self.state.skip_value(&self.input).map_err(|e| {
+            self.state.fail();
+            e
+        })
+    }
+}
+
+impl<'a> Iterator for JsonReader<'a> {
+    type Item = Result<JsonToken, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.state.next_token(&self.input, false) {
+            Ok(token) => token.map(|token| Ok(token.into())),
+            Err(e) => {
+                self.state.fail();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::NumberValue;
+    use crate::json_error::JsonErrorKind;
+    use lite_parser::impls::SimplePosition;
+
+    fn number(integer: u64) -> JsonToken {
+        JsonToken::Value(JsonValue::Number(NumberValue::new(integer, 0, 0, 0, false)))
+    }
+
+    #[test]
+    fn it_reads_tokens() {
+        let reader = JsonReader::new(r#" { "a": [1, "x", true, null, {}], "b": { "c": false } } "#);
+        assert_eq!(
+            reader.collect::<Result<Vec<_>, _>>(),
+            Ok(vec![
+                JsonToken::BeginObject,
+                JsonToken::Key("a".into()),
+                JsonToken::BeginArray,
+                number(1),
+                JsonToken::Value(JsonValue::String("x".into())),
+                JsonToken::Value(JsonValue::Boolean(true)),
+                JsonToken::Value(JsonValue::Null),
+                JsonToken::BeginObject,
+                JsonToken::EndObject,
+                JsonToken::EndArray,
+                JsonToken::Key("b".into()),
+                JsonToken::BeginObject,
+                JsonToken::Key("c".into()),
+                JsonToken::Value(JsonValue::Boolean(false)),
+                JsonToken::EndObject,
+                JsonToken::EndObject,
+            ])
+        );
+    }
+
+    #[test]
+    fn it_skips_values() {
+        let mut reader = JsonReader::new(
+            r#"{ "skip": { "a": [1, -2.5e3, "\"}"], "b": {} }, "keep": 2, "also": [3], "last": 4 }"#,
+        );
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginObject)));
+        assert_eq!(reader.next(), Some(Ok(JsonToken::Key("skip".into()))));
+        assert_eq!(reader.skip_value(), Ok(true));
+        assert_eq!(reader.next(), Some(Ok(JsonToken::Key("keep".into()))));
+        assert_eq!(reader.next(), Some(Ok(number(2))));
+        assert_eq!(reader.skip_value(), Ok(true));
+        assert_eq!(reader.next(), Some(Ok(JsonToken::Key("last".into()))));
+        assert_eq!(reader.next(), Some(Ok(number(4))));
+        assert_eq!(reader.skip_value(), Ok(false));
+        assert_eq!(reader.next(), Some(Ok(JsonToken::EndObject)));
+        assert_eq!(reader.skip_value(), Ok(false));
+        assert_eq!(reader.next(), None);
+
+        let mut reader = JsonReader::new("[4, {}]");
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginArray)));
+        assert_eq!(reader.next(), Some(Ok(number(4))));
+        assert_eq!(reader.skip_value(), Ok(true));
+        assert_eq!(reader.skip_value(), Ok(false));
+        assert_eq!(reader.next(), Some(Ok(JsonToken::EndArray)));
+        assert_eq!(reader.next(), None);
+
+        let mut reader = JsonReader::new("{}");
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginObject)));
+        assert_eq!(reader.skip_value(), Ok(false));
+        assert_eq!(reader.next(), Some(Ok(JsonToken::EndObject)));
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        let mut reader = JsonReader::new("[1 2]");
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginArray)));
+        assert_eq!(reader.next(), Some(Ok(number(1))));
+        let position = SimplePosition {
+            index: 3,
+            line: 0,
+            column: 3,
+        };
+        assert_eq!(
+            reader.next(),
+            Some(Err(JsonError {
+                kind: JsonErrorKind::UnexpectedChar,
+                position,
+                found: Some('2'),
+                expected: vec![",", "]"],
+                reasons: vec![(position, "ExpectChar")],
+            }))
+        );
+        assert_eq!(reader.next(), None);
+
+        let mut reader = JsonReader::new("[]]");
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginArray)));
+        assert_eq!(reader.next(), Some(Ok(JsonToken::EndArray)));
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::TrailingCharacters);
+        assert_eq!(error.expected, vec!["end of input"]);
+
+        let mut reader = JsonReader::with_options(
+            "[[]]",
+            ParserOptions {
+                max_nest_level: Some(1),
+                ..Default::default()
+            },
+        );
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginArray)));
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::NestingTooDeep);
+        assert_eq!(error.position.index, 1);
+
+        let mut reader = JsonReader::new(r#"{"a" 1}"#);
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginObject)));
+        assert_eq!(reader.next(), Some(Ok(JsonToken::Key("a".into()))));
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.expected, vec![":"]);
+        assert_eq!(error.found, Some('1'));
+
+        let mut reader = JsonReader::new(r#"["\x"]"#);
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginArray)));
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::InvalidEscape);
+        assert_eq!(error.position.index, 3);
+        assert_eq!(error.found, Some('x'));
+
+        let mut reader = JsonReader::new("[-");
+        assert_eq!(reader.next(), Some(Ok(JsonToken::BeginArray)));
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind, JsonErrorKind::UnexpectedEof);
+        assert_eq!(error.expected, vec!["digit"]);
+    }
+}
@@ -0,0 +1,379 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::json::GenericJsonValue;
+use crate::json_error::{Failure, JsonError, JsonErrorKind};
+use crate::json_parser::TreeBuilder;
+use crate::json_reader::ReaderState;
+use crate::object::{ObjectBackend, VecObjects};
+use lite_parser::{parser::ParserOptions, traits::Input};
+
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+#[derive(Clone)]
+pub enum StreamError {
+    /// The buffered input ends in the middle of the document; feed more to continue.
+    NeedMoreInput,
+    Invalid(JsonError),
+}
+
+/// How much of the pending token `TokenScan` has seen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    /// Whitespace, and after `separator` a `,` or `:`, before the token.
+    Before {
+        separator: bool,
+    },
+    /// Inside a string, right after an unescaped backslash if `escaped`.
+    String {
+        escaped: bool,
+    },
+    /// Whitespace after a string. A key is only complete once the following `:`
+    /// is available.
+    AfterString,
+    Number,
+    Literal,
+}
+
+/// Finds where the pending token ends, resuming where the previous feed stopped so
+/// that every byte is only scanned once.
+#[derive(Clone, Copy)]
+struct TokenScan {
+    scan: Scan,
+    /// The offset of the next byte to scan.
+    offset: usize,
+}
+
+impl TokenScan {
+    fn new(offset: usize) -> Self {
+        Self {
+            scan: Scan::Before { separator: false },
+            offset,
+        }
+    }
+
+    /// Returns the offset at which the pending token ends, or `None` if the buffer
+    /// ends before the token does.
+    fn token_end(&mut self, buffer: &[u8]) -> Option<usize> {
+        let is_whitespace = |b: u8| matches!(b, b' ' | b'\r' | b'\n' | b'\t');
+        while let Some(&b) = buffer.get(self.offset) {
+            self.scan = match (self.scan, b) {
+                (Scan::Before { .. }, b) if is_whitespace(b) => self.scan,
+                (Scan::Before { separator: false }, b',' | b':') => {
+                    Scan::Before { separator: true }
+                }
+                (Scan::Before { .. }, b'"') => Scan::String { escaped: false },
+                (Scan::Before { .. }, b'-' | b'0'..=b'9') => Scan::Number,
+                (Scan::Before { .. }, b'a'..=b'z') => Scan::Literal,
+                // Anything else is a single character token, such as a bracket, or an
+                // invalid one that the reader reports once all of its bytes are
+                // available.
+                (Scan::Before { .. }, lead) => {
+                    let width = match lead {
+                        0xC0..=0xDF => 2,
+                        0xE0..=0xEF => 3,
+                        0xF0..=0xF7 => 4,
+                        _ => 1,
+                    };
+                    return Some(self.offset + width).filter(|end| *end <= buffer.len());
+                }
+                (Scan::String { escaped: false }, b'"') => Scan::AfterString,
+                (Scan::String { escaped }, b) => Scan::String {
+                    escaped: b == b'\\' && !escaped,
+                },
+                (Scan::AfterString, b) if is_whitespace(b) => self.scan,
+                (Scan::Number, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') => self.scan,
+                (Scan::Literal, b) if b.is_ascii_alphabetic() => self.scan,
+                (Scan::AfterString | Scan::Number | Scan::Literal, _) => return Some(self.offset),
+            };
+            self.offset += 1;
+        }
+        None
+    }
+}
+
+/// Incremental parser for documents that arrive in chunks, such as streamed
+/// HTTP response bodies.
+///
+/// Objects store their members with backend `B`, which `for_backend` selects.
+pub struct JsonStreamParser<B: ObjectBackend = VecObjects> {
+    buffer: Vec<u8>,
+    state: ReaderState,
+    builder: TreeBuilder<String, GenericJsonValue<B>>,
+    value: Option<GenericJsonValue<B>>,
+    scan: TokenScan,
+    complete: bool,
+    error: Option<JsonError>,
+}
+
+impl<B: ObjectBackend> Default for JsonStreamParser<B> {
+    fn default() -> Self {
+        Self::for_backend(Default::default())
+    }
+}
+
+impl JsonStreamParser {
+    pub fn new() -> Self {
+        Self::with_options(Default::default())
+    }
+
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self::for_backend(options)
+    }
+}
+
+impl<B: ObjectBackend> JsonStreamParser<B> {
+    /// Creates a parser whose objects store their members with backend `B`, such
+    /// as `JsonStreamParser::<BTreeObjects>::for_backend(options)`.
+    pub fn for_backend(options: ParserOptions) -> Self {
+        Self {
+            buffer: Vec::new(),
+            state: ReaderState::new(options),
+            builder: TreeBuilder::new(),
+            value: None,
+            scan: TokenScan::new(0),
+            complete: false,
+            error: None,
+        }
+    }
+
+    /// Appends `chunk` to the input and parses as far as possible.
+    ///
+    /// Returns `Ok(())` once the root value is complete, after which `finish`
+    /// returns it, or `StreamError::NeedMoreInput` if the chunk ended before that.
+    /// Anything but whitespace after the root value is reported as soon as it is fed.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), StreamError> {
+        self.buffer.extend_from_slice(chunk);
+        self.process(false)
+    }
+
+    /// Signals the end of input and returns the parsed document.
+    pub fn finish(mut self) -> Result<GenericJsonValue<B>, StreamError> {
+        self.process(true)?;
+        self.value.ok_or(StreamError::NeedMoreInput)
+    }
+
+    fn fail(&mut self, error: JsonError) -> StreamError {
+        self.state.fail();
+        self.error = Some(error.clone());
+        StreamError::Invalid(error)
+    }
+
+    fn process(&mut self, finished: bool) -> Result<(), StreamError> {
+        if let Some(error) = &self.error {
+            return Err(StreamError::Invalid(error.clone()));
+        }
+        if self.complete {
+            let input = &self.buffer[..];
+            let mut pos = self.state.position;
+            while let Ok((c, next)) = input.next(pos) {
+                if !matches!(c, ' ' | '\r' | '\n' | '\t') {
+                    let failure = Failure::new(pos, "Expect end of input", vec!["end of input"])
+                        .with_kind(JsonErrorKind::TrailingCharacters);
+                    let error = JsonError::new(input, failure);
+                    return Err(self.fail(error));
+                }
+                pos = next;
+            }
+            self.state.position = pos;
+            self.compact();
+            return Ok(());
+        }
+        loop {
+            // Once the root value is done, whatever follows is an error.
+            if !finished && self.value.is_none() && self.scan.token_end(&self.buffer).is_none() {
+                self.compact();
+                return Err(StreamError::NeedMoreInput);
+            }
+            let token = match self.state.next_token(&&self.buffer[..], false) {
+                Ok(token) => token,
+                Err(e) => return Err(self.fail(e)),
+            };
+            self.scan = TokenScan::new(self.state.position.offset as usize);
+            match token {
+                Some(token) => self.value = self.builder.push(token),
+                None => {
+                    self.complete = true;
+                    self.compact();
+                    return Ok(());
+                }
+            }
+            if self.value.is_some() && !finished {
+                // The root value is done; report it unless trailing characters follow.
+                let rest = &self.buffer[self.state.position.offset as usize..];
+                if rest
+                    .iter()
+                    .all(|b| matches!(b, b' ' | b'\r' | b'\n' | b'\t'))
+                {
+                    self.state.fail();
+                    self.complete = true;
+                    self.compact();
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Drops the consumed part of the buffer.
+    fn compact(&mut self) {
+        let consumed = self.state.position.offset as usize;
+        self.buffer.drain(..consumed);
+        self.state.position.offset = 0;
+        self.scan.offset = self.scan.offset.saturating_sub(consumed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonValue;
+    use crate::json_error::JsonErrorKind;
+    use crate::json_parser::parse_json;
+    use lite_parser::impls::SimplePosition;
+
+    fn parse_in_chunks(input: &str, chunk_size: usize) -> Result<JsonValue, StreamError> {
+        let mut parser = JsonStreamParser::new();
+        for chunk in input.as_bytes().chunks(chunk_size) {
+            match parser.feed(chunk) {
+                Ok(()) | Err(StreamError::NeedMoreInput) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        parser.finish()
+    }
+
+    #[test]
+    fn it_parses_any_split() {
+        let input = r#" { "key": [1.5e-3, -20, "a\"b\u0041ü", true, false, null], "日本": {"x": {}}, "y": [] } "#;
+        let expected = parse_json(input).unwrap();
+        for chunk_size in 1..input.len() {
+            assert_eq!(parse_in_chunks(input, chunk_size), Ok(expected.clone()));
+        }
+        assert_eq!(
+            parse_in_chunks("1234", 1),
+            parse_json("1234").map_err(StreamError::Invalid)
+        );
+    }
+
+    #[test]
+    fn it_needs_more_input() {
+        let mut parser = JsonStreamParser::new();
+        assert_eq!(parser.feed(b"[tr"), Err(StreamError::NeedMoreInput));
+        assert_eq!(parser.feed(b"ue, 12"), Err(StreamError::NeedMoreInput));
+        assert_eq!(parser.feed(b"3"), Err(StreamError::NeedMoreInput));
+        assert_eq!(parser.feed(b"]  "), Ok(()));
+        assert_eq!(parser.feed(b"\n"), Ok(()));
+        assert_eq!(
+            parser.finish(),
+            Ok(JsonValue::Array(vec![
+                JsonValue::Boolean(true),
+                JsonValue::Number(crate::NumberValue::new(123, 0, 0, 0, false))
+            ]))
+        );
+
+        let mut parser = JsonStreamParser::new();
+        assert_eq!(parser.feed(b"{\"a\""), Err(StreamError::NeedMoreInput));
+        assert!(matches!(parser.finish(), Err(StreamError::Invalid(_))));
+    }
+
+    #[test]
+    fn it_waits_for_split_characters() {
+        let unexpected = |result: Result<(), StreamError>| match result {
+            Err(StreamError::Invalid(error)) => (error.kind, error.found),
+            other => panic!("unexpected {:?}", other),
+        };
+        let mut parser = JsonStreamParser::new();
+        assert_eq!(parser.feed(b"\xe6\x97"), Err(StreamError::NeedMoreInput));
+        assert_eq!(
+            unexpected(parser.feed(b"\xa5")),
+            (JsonErrorKind::UnexpectedChar, Some('日'))
+        );
+
+        let mut parser = JsonStreamParser::new();
+        assert_eq!(
+            parser.feed(b"[1, \xf0\x9f"),
+            Err(StreamError::NeedMoreInput)
+        );
+        assert_eq!(parser.feed(b"\x98"), Err(StreamError::NeedMoreInput));
+        assert_eq!(
+            unexpected(parser.feed(b"\x80]")),
+            (JsonErrorKind::UnexpectedChar, Some('😀'))
+        );
+    }
+
+    #[test]
+    fn it_reports_invalid_input() {
+        let mut parser = JsonStreamParser::new();
+        assert_eq!(parser.feed(b"[1,"), Err(StreamError::NeedMoreInput));
+        assert_eq!(
+            parser.feed(b" }"),
+            Err(StreamError::Invalid(JsonError {
+                kind: JsonErrorKind::UnexpectedChar,
+                position: SimplePosition {
+                    index: 4,
+                    line: 0,
+                    column: 4
+                },
+                found: Some('}'),
+                expected: vec!["{", "[", "string", "number", "true", "false", "null"],
+                reasons: vec![(
+                    SimplePosition {
+                        index: 4,
+                        line: 0,
+                        column: 4
+                    },
+                    "Value"
+                )]
+            }))
+        );
+        assert!(matches!(parser.feed(b"]"), Err(StreamError::Invalid(_))));
+
+        let mut parser = JsonStreamParser::new();
+        assert_eq!(parser.feed(b"{}"), Ok(()));
+        assert_eq!(
+            parser.feed(b" x"),
+            Err(StreamError::Invalid(JsonError {
+                kind: JsonErrorKind::TrailingCharacters,
+                position: SimplePosition {
+                    index: 3,
+                    line: 0,
+                    column: 3
+                },
+                found: Some('x'),
+                expected: vec!["end of input"],
+                reasons: vec![(
+                    SimplePosition {
+                        index: 3,
+                        line: 0,
+                        column: 3
+                    },
+                    "Expect end of input"
+                )]
+            }))
+        );
+
+        let trailing = |chunk: &[u8]| match JsonStreamParser::new().feed(chunk) {
+            Err(StreamError::Invalid(error)) => (error.kind, error.position.index),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(trailing(b"{} x"), (JsonErrorKind::TrailingCharacters, 3));
+        assert_eq!(trailing(b"1 2"), (JsonErrorKind::TrailingCharacters, 2));
+        assert_eq!(trailing(b"[]\"a"), (JsonErrorKind::TrailingCharacters, 2));
+    }
+
+    #[test]
+    fn it_scans_each_byte_once() {
+        let mut parser = JsonStreamParser::new();
+        assert_eq!(parser.feed(b"[\"ab\\"), Err(StreamError::NeedMoreInput));
+        // The string is pending, and scanning resumes after its last byte.
+        assert_eq!(parser.scan.offset, parser.buffer.len());
+        assert_eq!(parser.feed(b"\"c"), Err(StreamError::NeedMoreInput));
+        assert_eq!(parser.scan.offset, parser.buffer.len());
+        assert_eq!(parser.feed(b"\" ,  1"), Err(StreamError::NeedMoreInput));
+        assert_eq!(parser.scan.offset, parser.buffer.len());
+        assert_eq!(parser.feed(b"]"), Ok(()));
+        assert_eq!(parser.finish(), Ok(crate::json!(["ab\"c", 1])));
+    }
+}
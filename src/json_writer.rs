@@ -0,0 +1,303 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::json::{write_new_line_indent, write_string, FormatOptions, JsonValue, NumberValue};
+use crate::traits::{Serialize, Sink};
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq, Eq)]
+pub enum WriterError<E> {
+    /// The sink failed to accept output.
+    Sink(E),
+    /// The call is not valid at this point of the document, such as a value
+    /// without a key inside an object or `end_array` closing an object.
+    Misordered,
+}
+
+impl<E> From<E> for WriterError<E> {
+    fn from(error: E) -> Self {
+        WriterError::Sink(error)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+struct Frame {
+    container: Container,
+    count: usize,
+    has_key: bool,
+}
+
+/// Streaming serializer that writes a document to a `Sink` one token at a time,
+/// without building a `JsonValue` tree.
+///
+/// Containers are written across lines whenever `options.indent` is set, as
+/// `max_width` needs to see a whole container before writing it.
+pub struct JsonWriter<S> {
+    sink: S,
+    options: FormatOptions,
+    stack: Vec<Frame>,
+    root_written: bool,
+}
+
+impl<S: Sink> JsonWriter<S> {
+    pub fn new(sink: S) -> Self {
+        Self::with_options(sink, Default::default())
+    }
+
+    pub fn with_options(sink: S, options: FormatOptions) -> Self {
+        Self {
+            sink,
+            options,
+            stack: Vec::new(),
+            root_written: false,
+        }
+    }
+
+    /// Writes the separator and indentation that precede a value, or fails if
+    /// no value is allowed here.
+    fn before_value(&mut self) -> Result<(), WriterError<S::Error>> {
+        let level = self.stack.len() as u32;
+        match self.stack.last_mut() {
+            None if self.root_written => Err(WriterError::Misordered),
+            None => {
+                self.root_written = true;
+                Ok(())
+            }
+            Some(frame) => match frame.container {
+                Container::Object if !frame.has_key => Err(WriterError::Misordered),
+                Container::Object => {
+                    frame.has_key = false;
+                    Ok(())
+                }
+                Container::Array => {
+                    frame.count += 1;
+                    if frame.count > 1 {
+                        self.sink.write_str(",")?;
+                    }
+                    if self.options.indent > 0 {
+                        write_new_line_indent(&mut self.sink, &self.options, level)?;
+                    }
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn begin(&mut self, container: Container) -> Result<(), WriterError<S::Error>> {
+        self.before_value()?;
+        self.sink.write_str(match container {
+            Container::Object => "{",
+            Container::Array => "[",
+        })?;
+        self.stack.push(Frame {
+            container,
+            count: 0,
+            has_key: false,
+        });
+        Ok(())
+    }
+
+    fn end(&mut self, container: Container) -> Result<(), WriterError<S::Error>> {
+        match self.stack.last() {
+            Some(frame) if frame.container == container && !frame.has_key => {}
+            _ => return Err(WriterError::Misordered),
+        }
+        let frame = self.stack.pop().unwrap();
+        if frame.count > 0 && self.options.indent > 0 {
+            write_new_line_indent(&mut self.sink, &self.options, self.stack.len() as u32)?;
+        }
+        self.sink.write_str(match container {
+            Container::Object => "}",
+            Container::Array => "]",
+        })?;
+        Ok(())
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriterError<S::Error>> {
+        self.begin(Container::Object)
+    }
+
+    pub fn end_object(&mut self) -> Result<(), WriterError<S::Error>> {
+        self.end(Container::Object)
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriterError<S::Error>> {
+        self.begin(Container::Array)
+    }
+
+    pub fn end_array(&mut self) -> Result<(), WriterError<S::Error>> {
+        self.end(Container::Array)
+    }
+
+    /// Writes the key of the next object member.
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError<S::Error>> {
+        let level = self.stack.len() as u32;
+        let frame = match self.stack.last_mut() {
+            Some(frame) if frame.container == Container::Object && !frame.has_key => frame,
+            _ => return Err(WriterError::Misordered),
+        };
+        frame.has_key = true;
+        frame.count += 1;
+        if frame.count > 1 {
+            self.sink.write_str(",")?;
+        }
+        if self.options.indent > 0 {
+            write_new_line_indent(&mut self.sink, &self.options, level)?;
+        }
+        write_string(&mut self.sink, key, &self.options)?;
+        self.sink.write_str(if self.options.space_after_colon {
+            ": "
+        } else {
+            ":"
+        })?;
+        Ok(())
+    }
+
+    pub fn string(&mut self, value: &str) -> Result<(), WriterError<S::Error>> {
+        self.before_value()?;
+        write_string(&mut self.sink, value, &self.options)?;
+        Ok(())
+    }
+
+    pub fn number(&mut self, value: NumberValue) -> Result<(), WriterError<S::Error>> {
+        self.before_value()?;
+        value.write_to(&mut self.sink, &self.options, 0)?;
+        Ok(())
+    }
+
+    pub fn bool(&mut self, value: bool) -> Result<(), WriterError<S::Error>> {
+        self.before_value()?;
+        self.sink.write_str(if value { "true" } else { "false" })?;
+        Ok(())
+    }
+
+    pub fn null(&mut self) -> Result<(), WriterError<S::Error>> {
+        self.before_value()?;
+        self.sink.write_str("null")?;
+        Ok(())
+    }
+
+    /// Writes a complete value, such as a small subtree that was built in memory.
+    pub fn value(&mut self, value: &JsonValue) -> Result<(), WriterError<S::Error>> {
+        self.before_value()?;
+        value.write_to(&mut self.sink, &self.options, self.stack.len() as u32)?;
+        Ok(())
+    }
+
+    /// Checks that the document is complete, writes the trailing newline if
+    /// configured and returns the sink.
+    pub fn finish(mut self) -> Result<S, WriterError<S::Error>> {
+        if !self.root_written || !self.stack.is_empty() {
+            return Err(WriterError::Misordered);
+        }
+        if self.options.trailing_newline {
+            self.sink.write_str(self.options.new_line())?;
+        }
+        Ok(self.sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parse_json;
+    use crate::sink::BufferOverflow;
+
+    fn write_document<S: Sink>(writer: &mut JsonWriter<S>) -> Result<(), WriterError<S::Error>> {
+        writer.begin_object()?;
+        writer.key("name")?;
+        writer.string("li\"te")?;
+        writer.key("tags")?;
+        writer.begin_array()?;
+        writer.number(NumberValue::new(1, 5, 1, 0, true))?;
+        writer.begin_array()?;
+        writer.end_array()?;
+        writer.begin_object()?;
+        writer.key("x")?;
+        writer.bool(true)?;
+        writer.end_object()?;
+        writer.end_array()?;
+        writer.key("empty")?;
+        writer.begin_object()?;
+        writer.end_object()?;
+        writer.key("value")?;
+        writer.value(&parse_json("[null, {\"a\": false}]").unwrap())?;
+        writer.key("none")?;
+        writer.null()?;
+        writer.end_object()
+    }
+
+    // Depends on members staying in document order.
+    #[test]
+    fn it_writes_documents() {
+        let expected = parse_json(
+            r#"{"name":"li\"te","tags":[-1.5,[],{"x":true}],"empty":{},"value":[null,{"a":false}],"none":null}"#,
+        )
+        .unwrap();
+        for options in [
+            FormatOptions::default(),
+            FormatOptions::pretty(2),
+            FormatOptions {
+                trailing_newline: true,
+                ..FormatOptions::pretty(4)
+            },
+        ]
+        .iter()
+        {
+            let mut writer = JsonWriter::with_options(Vec::new(), options.clone());
+            write_document(&mut writer).unwrap();
+            assert_eq!(writer.finish(), Ok(expected.serialize_with(options)));
+        }
+    }
+
+    #[test]
+    fn it_writes_to_fixed_buffers() {
+        let mut buffer = [0u8; 128];
+        let mut writer = JsonWriter::new(&mut buffer[..]);
+        write_document(&mut writer).unwrap();
+        let remaining = writer.finish().unwrap().len();
+        let written = core::str::from_utf8(&buffer[..128 - remaining]).unwrap();
+        assert!(parse_json(written).is_ok());
+
+        let mut buffer = [0u8; 16];
+        let mut writer = JsonWriter::new(&mut buffer[..]);
+        assert_eq!(
+            write_document(&mut writer),
+            Err(WriterError::Sink(BufferOverflow))
+        );
+    }
+
+    #[test]
+    fn it_rejects_misordered_calls() {
+        let mut writer = JsonWriter::new(Vec::new());
+        assert_eq!(writer.key("a"), Err(WriterError::Misordered));
+        assert_eq!(writer.end_array(), Err(WriterError::Misordered));
+        writer.begin_object().unwrap();
+        assert_eq!(writer.null(), Err(WriterError::Misordered));
+        assert_eq!(writer.end_array(), Err(WriterError::Misordered));
+        writer.key("a").unwrap();
+        assert_eq!(writer.key("b"), Err(WriterError::Misordered));
+        assert_eq!(writer.end_object(), Err(WriterError::Misordered));
+        writer.bool(false).unwrap();
+        writer.end_object().unwrap();
+        assert_eq!(writer.null(), Err(WriterError::Misordered));
+        assert_eq!(writer.finish(), Ok(b"{\"a\":false}".to_vec()));
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_array().unwrap();
+        assert_eq!(writer.key("a"), Err(WriterError::Misordered));
+        assert!(matches!(writer.finish(), Err(WriterError::Misordered)));
+        assert!(matches!(
+            JsonWriter::new(Vec::new()).finish(),
+            Err(WriterError::Misordered)
+        ));
+    }
+}
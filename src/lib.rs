@@ -1,9 +1,28 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod convert;
 pub mod json;
+pub mod json_error;
 pub mod json_parser;
+pub mod json_reader;
+pub mod json_stream;
+pub mod json_writer;
+mod macros;
+pub mod object;
+pub mod sink;
 pub mod traits;
+pub mod wtf8;
 
+pub use crate::convert::*;
 pub use crate::json::*;
+pub use crate::json_error::*;
 pub use crate::json_parser::*;
+pub use crate::json_reader::*;
+pub use crate::json_stream::*;
+pub use crate::json_writer::*;
+#[doc(hidden)]
+pub use crate::macros::__private;
+pub use crate::object::*;
+pub use crate::sink::*;
 pub use crate::traits::*;
+pub use crate::wtf8::*;
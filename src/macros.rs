@@ -0,0 +1,212 @@
+/// Builds a `JsonValue` from JSON-like syntax.
+///
+/// Values that are not `null`, `true`, `false`, arrays or objects are Rust
+/// expressions converted with `Into<JsonValue>`. Object keys are string literals,
+/// variables or parenthesized expressions that implement `AsRef<str>`.
+///
+/// Floats are converted with `TryFrom`, and NaN or an infinity panics.
+///
+/// ```
+/// use lite_json::{json, Serialize};
+///
+/// let name = "lite";
+/// let value = json!({
+///     (name.to_uppercase()): { "deps": null, "size": 1.5 },
+///     "name": name,
+///     "stable": false,
+///     "version": [0, 2 + 1]
+/// });
+/// assert_eq!(
+///     value.serialize(),
+///     br#"{"LITE":{"deps":null,"size":1.5},"name":"lite","stable":false,"version":[0,3]}"#
+/// );
+/// ```
+#[macro_export]
+macro_rules! json {
+    (null) => {
+        $crate::JsonValue::Null
+    };
+    (true) => {
+        $crate::JsonValue::Boolean(true)
+    };
+    (false) => {
+        $crate::JsonValue::Boolean(false)
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::JsonValue::Array($crate::json_internal!(@array [] $($tt)*))
+    };
+    ({ $($tt:tt)* }) => {
+        $crate::JsonValue::Object($crate::__private::object($crate::json_internal!(@object [] $($tt)*)))
+    };
+    ($other:expr) => {
+        $crate::__private::value($other)
+    };
+}
+
+/// Munches the elements of `json!` arrays and objects. The `@array` and `@object`
+/// states expect the next element, the `_sep` states a comma or the end.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    (@array [$($elems:expr,)*]) => {
+        $crate::__private::vec![$($elems,)*]
+    };
+    (@array_sep [$($elems:expr,)*]) => {
+        $crate::__private::vec![$($elems,)*]
+    };
+    (@array_sep [$($elems:expr,)*] , $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)*] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json_internal!(@array_sep [$($elems,)* $crate::json!(null),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::json_internal!(@array_sep [$($elems,)* $crate::json!(true),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::json_internal!(@array_sep [$($elems,)* $crate::json!(false),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [ $($array:tt)* ] $($rest:tt)*) => {
+        $crate::json_internal!(@array_sep [$($elems,)* $crate::json!([$($array)*]),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] { $($object:tt)* } $($rest:tt)*) => {
+        $crate::json_internal!(@array_sep [$($elems,)* $crate::json!({$($object)*}),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $value:expr, $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json!($value),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $value:expr) => {
+        $crate::json_internal!(@array_sep [$($elems,)* $crate::json!($value),])
+    };
+
+    (@object [$($entries:expr,)*]) => {
+        $crate::__private::vec![$($entries,)*]
+    };
+    (@object_sep [$($entries:expr,)*]) => {
+        $crate::__private::vec![$($entries,)*]
+    };
+    (@object_sep [$($entries:expr,)*] , $($rest:tt)*) => {
+        $crate::json_internal!(@object [$($entries,)*] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : null $($rest:tt)*) => {
+        $crate::json_internal!(@object_sep [$($entries,)* ($crate::__private::key(&$key), $crate::json!(null)),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : true $($rest:tt)*) => {
+        $crate::json_internal!(@object_sep [$($entries,)* ($crate::__private::key(&$key), $crate::json!(true)),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : false $($rest:tt)*) => {
+        $crate::json_internal!(@object_sep [$($entries,)* ($crate::__private::key(&$key), $crate::json!(false)),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : [ $($array:tt)* ] $($rest:tt)*) => {
+        $crate::json_internal!(@object_sep [$($entries,)* ($crate::__private::key(&$key), $crate::json!([$($array)*])),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : { $($object:tt)* } $($rest:tt)*) => {
+        $crate::json_internal!(@object_sep [$($entries,)* ($crate::__private::key(&$key), $crate::json!({$($object)*})),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : $value:expr, $($rest:tt)*) => {
+        $crate::json_internal!(@object [$($entries,)* ($crate::__private::key(&$key), $crate::json!($value)),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : $value:expr) => {
+        $crate::json_internal!(@object_sep [$($entries,)* ($crate::__private::key(&$key), $crate::json!($value)),])
+    };
+}
+
+/// Items used by the expansion of `json!`, so that it works without `std`.
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(not(feature = "std"))]
+    extern crate alloc;
+
+    #[cfg(not(feature = "std"))]
+    pub use alloc::vec;
+    #[cfg(feature = "std")]
+    pub use std::vec;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::string::String;
+
+    use self::vec::Vec;
+    use crate::json::{JsonObject, JsonValue};
+    use crate::object::ObjectMap;
+    use core::convert::TryFrom;
+
+    pub fn key<K: AsRef<str> + ?Sized>(key: &K) -> String {
+        key.as_ref().into()
+    }
+
+    pub fn object(members: Vec<(String, JsonValue)>) -> JsonObject {
+        JsonObject::from_members(members)
+    }
+
+    /// Converts the expressions in `json!`, which also accepts finite floats.
+    pub trait ToValue {
+        fn to_value(self) -> JsonValue;
+    }
+
+    impl<T: Into<JsonValue>> ToValue for T {
+        fn to_value(self) -> JsonValue {
+            self.into()
+        }
+    }
+
+    impl ToValue for f64 {
+        fn to_value(self) -> JsonValue {
+            JsonValue::try_from(self).expect("json! cannot represent NaN or infinities")
+        }
+    }
+
+    impl ToValue for f32 {
+        fn to_value(self) -> JsonValue {
+            JsonValue::try_from(self).expect("json! cannot represent NaN or infinities")
+        }
+    }
+
+    pub fn value<T: ToValue>(value: T) -> JsonValue {
+        value.to_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::JsonValue;
+    use crate::json_parser::parse_json;
+
+    #[test]
+    fn it_builds_values() {
+        assert_eq!(json!(null), JsonValue::Null);
+        assert_eq!(json!([]), JsonValue::Array(vec![]));
+        assert_eq!(json!({}), JsonValue::Object(Default::default()));
+        assert_eq!(json!("a"), parse_json(r#""a""#).unwrap());
+        assert_eq!(json!(-1), parse_json("-1").unwrap());
+
+        let key = "k";
+        let items = vec![1u8, 2];
+        let value = json!({
+            "null": null,
+            "bools": [true, false,],
+            "numbers": [1, -2, 2.5, 1u64 << 40, 0.1f32],
+            key: items.clone(),
+            (std::format!("{}{}", key, 2)): { "nested": [[], {}, [null]] },
+            "option": Some("x"),
+            "last": { "a": 1, "b": false }
+        });
+        let expected = parse_json(
+            r#"{
+                "null": null,
+                "bools": [true, false],
+                "numbers": [1, -2, 2.5, 1099511627776, 0.1],
+                "k": [1, 2],
+                "k2": { "nested": [[], {}, [null]] },
+                "option": "x",
+                "last": { "a": 1, "b": false }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(value, expected);
+    }
+    #[test]
+    #[should_panic(expected = "json! cannot represent NaN or infinities")]
+    fn it_rejects_non_finite_floats() {
+        json!([1.0, f64::NAN]);
+    }
+}
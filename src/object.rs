@@ -0,0 +1,330 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "object_btree")]
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "object_btree")]
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use crate::json::GenericJsonValue;
+#[cfg(feature = "std")]
+use core::fmt::Debug;
+
+/// Selects how `GenericJsonValue` stores the members of objects.
+///
+/// - `VecObjects`, used by `JsonValue`, keeps a `Vec` of members in document order,
+///   which keeps duplicate keys and looks keys up with a linear scan.
+/// - `BTreeObjects`, used by `BTreeJsonValue` with the `object_btree` feature, keeps
+///   a `BTreeMap` sorted by key.
+/// - `IndexObjects`, used by `IndexJsonValue` with the `object_indexmap` feature,
+///   keeps an `IndexMap` in insertion order with hashed lookups.
+///
+/// Enabling a feature only adds its backend; `JsonValue` always uses `VecObjects`.
+pub trait ObjectBackend: Sized {
+    /// The members of an object.
+    #[cfg(feature = "std")]
+    type Object: ObjectMap<Value = GenericJsonValue<Self>> + Clone + PartialEq + Debug;
+    /// The members of an object.
+    #[cfg(not(feature = "std"))]
+    type Object: ObjectMap<Value = GenericJsonValue<Self>> + Clone + PartialEq;
+}
+
+/// Stores object members in a `Vec` in document order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VecObjects;
+
+impl ObjectBackend for VecObjects {
+    type Object = JsonObject;
+}
+
+/// The members of a `JsonValue` object, in document order.
+pub type JsonObject = Vec<(String, GenericJsonValue<VecObjects>)>;
+
+/// Stores object members in a `BTreeMap` sorted by key.
+#[cfg(feature = "object_btree")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BTreeObjects;
+
+#[cfg(feature = "object_btree")]
+impl ObjectBackend for BTreeObjects {
+    type Object = BTreeJsonObject;
+}
+
+/// The members of a `BTreeJsonValue` object, sorted by key.
+#[cfg(feature = "object_btree")]
+pub type BTreeJsonObject = BTreeMap<String, GenericJsonValue<BTreeObjects>>;
+
+/// Stores object members in an `IndexMap` in insertion order.
+#[cfg(feature = "object_indexmap")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexObjects;
+
+#[cfg(feature = "object_indexmap")]
+impl ObjectBackend for IndexObjects {
+    type Object = IndexJsonObject;
+}
+
+/// The members of an `IndexJsonValue` object, in insertion order with hashed lookups.
+#[cfg(feature = "object_indexmap")]
+pub type IndexJsonObject =
+    indexmap::IndexMap<String, GenericJsonValue<IndexObjects>, foldhash::fast::RandomState>;
+
+/// The operations the parser, serializer and `GenericJsonValue` need from the
+/// members of an object.
+///
+/// Maps cannot hold duplicate keys, so every representation resolves them the same
+/// way as lookups in the `Vec`: the first member with a key wins.
+pub trait ObjectMap: Default {
+    /// The value of a member.
+    type Value;
+
+    /// Builds an object from members in document order.
+    fn from_members(members: Vec<(String, Self::Value)>) -> Self {
+        let mut object = Self::default();
+        for (key, value) in members {
+            object.push_member(key, value);
+        }
+        object
+    }
+
+    /// Adds a member read from a document, keeping an earlier member with the same
+    /// key if the representation cannot hold both.
+    fn push_member(&mut self, key: String, value: Self::Value);
+
+    /// Returns the number of members.
+    fn len(&self) -> usize;
+
+    /// Returns a boolean indicating whether there are no members.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value of the first member named `key`.
+    fn member(&self, key: &str) -> Option<&Self::Value>;
+
+    /// Returns the value of the first member named `key` mutably.
+    fn member_mut(&mut self, key: &str) -> Option<&mut Self::Value>;
+
+    /// Returns the value of the first member named `key`, adding the result of `f`
+    /// under that key if there is none.
+    fn member_or_insert_with(
+        &mut self,
+        key: &str,
+        f: impl FnOnce() -> Self::Value,
+    ) -> &mut Self::Value;
+
+    /// Sets the value of the member named `key`, returning the previous value.
+    ///
+    /// Backends that keep duplicate keys replace the first member in place and
+    /// remove the later ones, so that the object ends up with a single `key`.
+    fn set_member(&mut self, key: &str, value: Self::Value) -> Option<Self::Value> {
+        match self.member_mut(key) {
+            Some(v) => Some(core::mem::replace(v, value)),
+            None => {
+                self.member_or_insert_with(key, || value);
+                None
+            }
+        }
+    }
+
+    /// Removes every member named `key`, returning the value of the first.
+    fn remove_member(&mut self, key: &str) -> Option<Self::Value>;
+
+    /// Keeps only the members for which `f` returns true.
+    fn retain_members(&mut self, f: impl FnMut(&str, &mut Self::Value) -> bool);
+
+    /// Returns an iterator over the members in the order they are serialized.
+    fn members(&self) -> impl Iterator<Item = (&str, &Self::Value)>;
+}
+
+impl<V> ObjectMap for Vec<(String, V)> {
+    type Value = V;
+
+    fn from_members(members: Vec<(String, V)>) -> Self {
+        members
+    }
+
+    fn push_member(&mut self, key: String, value: V) {
+        self.push((key, value));
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn member(&self, key: &str) -> Option<&V> {
+        self.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn member_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn member_or_insert_with(&mut self, key: &str, f: impl FnOnce() -> V) -> &mut V {
+        let index = match self.iter().position(|(k, _)| k == key) {
+            Some(index) => index,
+            None => {
+                self.push((key.into(), f()));
+                self.len() - 1
+            }
+        };
+        &mut self[index].1
+    }
+
+    fn set_member(&mut self, key: &str, value: V) -> Option<V> {
+        let index = match self.iter().position(|(k, _)| k == key) {
+            Some(index) => index,
+            None => {
+                self.push((key.into(), value));
+                return None;
+            }
+        };
+        let previous = core::mem::replace(&mut self[index].1, value);
+        let mut position = 0;
+        self.retain(|(k, _)| {
+            position += 1;
+            position <= index + 1 || k != key
+        });
+        Some(previous)
+    }
+
+    fn remove_member(&mut self, key: &str) -> Option<V> {
+        let index = self.iter().position(|(k, _)| k == key)?;
+        let (_, value) = self.remove(index);
+        self.retain(|(k, _)| k != key);
+        Some(value)
+    }
+
+    fn retain_members(&mut self, mut f: impl FnMut(&str, &mut V) -> bool) {
+        self.retain_mut(|(k, v)| f(k, v));
+    }
+
+    fn members(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+/// Implements `ObjectMap` for a map type with the `BTreeMap` API.
+#[cfg(any(feature = "object_btree", feature = "object_indexmap"))]
+macro_rules! map_object {
+    ([$($generics:tt)*] $map:ty, $remove:ident) => {
+        impl<$($generics)*> ObjectMap for $map {
+            type Value = V;
+
+            fn push_member(&mut self, key: String, value: V) {
+                self.entry(key).or_insert(value);
+            }
+
+            fn len(&self) -> usize {
+                self.len()
+            }
+
+            fn member(&self, key: &str) -> Option<&V> {
+                self.get(key)
+            }
+
+            fn member_mut(&mut self, key: &str) -> Option<&mut V> {
+                self.get_mut(key)
+            }
+
+            fn member_or_insert_with(
+                &mut self,
+                key: &str,
+                f: impl FnOnce() -> V,
+            ) -> &mut V {
+                self.entry(key.into()).or_insert_with(f)
+            }
+
+            fn remove_member(&mut self, key: &str) -> Option<V> {
+                self.$remove(key)
+            }
+
+            fn retain_members(&mut self, mut f: impl FnMut(&str, &mut V) -> bool) {
+                self.retain(|k, v| f(k, v));
+            }
+
+            fn members(&self) -> impl Iterator<Item = (&str, &V)> {
+                self.iter().map(|(k, v)| (k.as_str(), v))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "object_btree")]
+map_object!([V] BTreeMap<String, V>, remove);
+
+// `shift_remove` keeps the remaining members in order.
+#[cfg(feature = "object_indexmap")]
+map_object!(
+    [V, S: core::hash::BuildHasher + Default] indexmap::IndexMap<String, V, S>,
+    shift_remove
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::{parse_json, parse_json_as};
+    use crate::traits::Serialize;
+
+    const INPUT: &str = r#"{"b": 1, "a": {"y": [], "x": null}, "b": 2, "c": true}"#;
+
+    /// Edits `INPUT` parsed with backend `B`, returning the keys and serialization.
+    fn edit<B: ObjectBackend>() -> (Vec<String>, Vec<u8>) {
+        let mut value = parse_json_as::<B>(INPUT, Default::default()).unwrap();
+        assert_eq!(value["b"], GenericJsonValue::from(1));
+        assert_eq!(value["a"]["x"], GenericJsonValue::Null);
+        value.insert("d", "new");
+        value.remove("c");
+        let keys = value.keys().map(String::from).collect();
+        let serialized = value.serialize();
+
+        let reparsed = parse_json_as::<B>(
+            core::str::from_utf8(&serialized).unwrap(),
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(reparsed, value);
+        assert_eq!(value.insert("b", 3), Some(GenericJsonValue::from(1)));
+        assert_eq!(value.keys().filter(|k| *k == "b").count(), 1);
+        assert_eq!(value["b"], GenericJsonValue::from(3));
+        (keys, serialized)
+    }
+
+    #[test]
+    fn it_works_with_any_backend() {
+        let (keys, serialized) = edit::<VecObjects>();
+        assert_eq!(keys, ["b", "a", "b", "d"]);
+        assert_eq!(
+            serialized,
+            br#"{"b":1,"a":{"y":[],"x":null},"b":2,"d":"new"}"#
+        );
+        let borrowed = crate::parse_json_borrowed(r#"{"b": 1, "b": 2}"#).unwrap();
+        assert_eq!(borrowed.into_owned()["b"], crate::json!(1));
+
+        #[cfg(feature = "object_btree")]
+        {
+            let (keys, serialized) = edit::<BTreeObjects>();
+            assert_eq!(keys, ["a", "b", "d"]);
+            assert_eq!(serialized, br#"{"a":{"x":null,"y":[]},"b":1,"d":"new"}"#);
+        }
+        #[cfg(feature = "object_indexmap")]
+        {
+            let (keys, serialized) = edit::<IndexObjects>();
+            assert_eq!(keys, ["b", "a", "d"]);
+            assert_eq!(serialized, br#"{"b":1,"a":{"y":[],"x":null},"d":"new"}"#);
+        }
+    }
+
+    #[test]
+    fn features_do_not_change_json_value() {
+        let value = parse_json(INPUT).unwrap();
+        let object: &JsonObject = value.as_object().unwrap();
+        assert_eq!(object.len(), 4);
+        assert_eq!(object[2], ("b".into(), crate::json!(2)));
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+use crate::traits::Sink;
+use core::convert::Infallible;
+use core::fmt;
+
+impl Sink for Vec<u8> {
+    type Error = Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl Sink for String {
+    type Error = Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+/// Error returned when a fixed-capacity buffer is too small for the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferOverflow;
+
+/// Writes into the front of the slice and advances it past the written bytes, like
+/// `std::io::Write` for `&mut [u8]`. Output that does not fit is not written.
+impl Sink for &mut [u8] {
+    type Error = BufferOverflow;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        let bytes = s.as_bytes();
+        if bytes.len() > self.len() {
+            return Err(BufferOverflow);
+        }
+        let (head, tail) = core::mem::take(self).split_at_mut(bytes.len());
+        head.copy_from_slice(bytes);
+        *self = tail;
+        Ok(())
+    }
+}
+
+/// Adapts a `core::fmt::Write`, such as a `Formatter`, into a `Sink`.
+pub struct FmtSink<W>(pub W);
+
+impl<W: fmt::Write> Sink for FmtSink<W> {
+    type Error = fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.0.write_str(s)
+    }
+}
+
+/// Adapts a `std::io::Write`, such as a file or socket, into a `Sink`.
+///
+/// Writes are passed straight through, so wrap unbuffered writers in a `BufWriter`.
+#[cfg(feature = "std")]
+pub struct IoSink<W>(pub W);
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Sink for IoSink<W> {
+    type Error = std::io::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.0.write_all(s.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parse_json;
+    use crate::traits::Serialize;
+
+    #[test]
+    fn it_writes_to_sinks() {
+        let input = r#"{"a":[1,-2.5e-3,"x\n"],"b":{"c":null,"d":true}}"#;
+        let value = parse_json(input).unwrap();
+        let options = Default::default();
+
+        let mut string = String::new();
+        value.write_to(&mut string, &options, 0).unwrap();
+        assert_eq!(string, input);
+
+        assert_eq!(format!("{}", value), input);
+        assert_eq!(
+            format!("{:#}", parse_json("[1,{}]").unwrap()),
+            "[\n    1,\n    {}\n]"
+        );
+
+        let mut io = IoSink(Vec::new());
+        value.write_to(&mut io, &options, 0).unwrap();
+        assert_eq!(io.0, input.as_bytes());
+
+        let mut buffer = [0u8; 64];
+        let mut out = &mut buffer[..];
+        value.write_to(&mut out, &options, 0).unwrap();
+        let remaining = out.len();
+        assert_eq!(&buffer[..64 - remaining], input.as_bytes());
+
+        let mut buffer = [0u8; 16];
+        assert_eq!(
+            value.write_to(&mut &mut buffer[..], &options, 0),
+            Err(BufferOverflow)
+        );
+    }
+}
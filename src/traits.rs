@@ -2,18 +2,150 @@
 extern crate alloc;
 
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
+use crate::json::{FormatOptions, NumberValue};
+use crate::wtf8::Wtf8Buf;
+use core::ops::ControlFlow;
+
+/// Destination for serialized JSON. Output is always written as complete UTF-8
+/// sequences.
+pub trait Sink {
+    type Error;
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error>;
+}
+
+/// Serializes a value as JSON.
+///
+/// Only `serialize_to` is required. The other methods are provided on top of
+/// it, and implementations may override them to honour every `FormatOptions`
+/// field or to write to a `Sink` without an intermediate buffer.
 pub trait Serialize {
     fn serialize(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        self.serialize_to(&mut res, 0, 0);
-        res
+        self.serialize_with(&Default::default())
     }
     fn format(&self, indent: u32) -> Vec<u8> {
+        self.serialize_with(&FormatOptions::pretty(indent))
+    }
+    fn serialize_with(&self, options: &FormatOptions) -> Vec<u8> {
         let mut res = Vec::new();
-        self.serialize_to(&mut res, indent, 0);
+        self.serialize_to_with(&mut res, options, 0);
+        if options.trailing_newline {
+            res.extend_from_slice(options.new_line().as_bytes());
+        }
         res
     }
     fn serialize_to(&self, buffer: &mut Vec<u8>, indent: u32, level: u32);
+    /// Defaults to `serialize_to` with `options.indent`, ignoring the other
+    /// options.
+    fn serialize_to_with(&self, buffer: &mut Vec<u8>, options: &FormatOptions, level: u32) {
+        self.serialize_to(buffer, options.indent, level);
+    }
+    /// Writes the value as a whole document, followed by a line ending if
+    /// `options.trailing_newline` is set.
+    fn write_document<S: Sink>(&self, sink: &mut S, options: &FormatOptions) -> Result<(), S::Error>
+    where
+        Self: Sized,
+    {
+        self.write_to(sink, options, 0)?;
+        if options.trailing_newline {
+            sink.write_str(options.new_line())?;
+        }
+        Ok(())
+    }
+    /// Writes the value to `sink`. Defaults to serializing into a buffer first.
+    fn write_to<S: Sink>(
+        &self,
+        sink: &mut S,
+        options: &FormatOptions,
+        level: u32,
+    ) -> Result<(), S::Error>
+    where
+        Self: Sized,
+    {
+        let mut buffer = Vec::new();
+        self.serialize_to_with(&mut buffer, options, level);
+        sink.write_str(&String::from_utf8_lossy(&buffer))
+    }
+}
+
+/// Receives parse events from `parse_json_with_handler` in document order.
+///
+/// All callbacks default to doing nothing and continuing, so handlers only implement
+/// the events they need. Returning `ControlFlow::Break` from a callback stops the
+/// parse, which then returns `Ok(())` without reading the rest of the input.
+pub trait JsonHandler {
+    fn start_object(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    fn key(&mut self, _key: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    /// Receives a key that keeps a lone surrogate under `LoneSurrogate::Preserve`.
+    /// Defaults to `key` with each lone surrogate replaced by U+FFFD.
+    fn wtf8_key(&mut self, key: &Wtf8Buf) -> ControlFlow<()> {
+        self.key(&key.to_string_lossy())
+    }
+    fn end_object(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    fn start_array(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    fn end_array(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    fn string(&mut self, _value: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    /// Receives a string that keeps a lone surrogate under `LoneSurrogate::Preserve`.
+    /// Defaults to `string` with each lone surrogate replaced by U+FFFD.
+    fn wtf8_string(&mut self, value: &Wtf8Buf) -> ControlFlow<()> {
+        self.string(&value.to_string_lossy())
+    }
+    fn number(&mut self, _value: NumberValue) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    fn bool(&mut self, _value: bool) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+    fn null(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonValue;
+
+    struct Point(i32, i32);
+
+    impl Serialize for Point {
+        fn serialize_to(&self, buffer: &mut Vec<u8>, _indent: u32, _level: u32) {
+            buffer.extend_from_slice(format!("[{},{}]", self.0, self.1).as_bytes());
+        }
+    }
+
+    #[test]
+    fn it_serializes_with_only_serialize_to() {
+        let point = Point(1, -2);
+        assert_eq!(point.serialize(), b"[1,-2]");
+
+        let mut string = String::new();
+        point
+            .write_document(
+                &mut string,
+                &FormatOptions {
+                    trailing_newline: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(string, "[1,-2]\n");
+
+        let values: [&dyn Serialize; 2] = [&point, &JsonValue::Boolean(true)];
+        let serialized: Vec<Vec<u8>> = values.iter().map(|value| value.serialize()).collect();
+        assert_eq!(serialized, [b"[1,-2]".to_vec(), b"true".to_vec()]);
+    }
 }
@@ -0,0 +1,183 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::{borrow::Cow, string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+
+use crate::json::{write_string_contents, write_unicode_escape, FormatOptions};
+use crate::traits::{Serialize, Sink};
+
+/// A string that may hold lone UTF-16 surrogates, encoded as WTF-8.
+///
+/// WTF-8 is UTF-8 that also encodes surrogate code points, the same way as any
+/// other code point. Strings without lone surrogates are plain UTF-8. Parsing with
+/// `LoneSurrogate::Preserve` reports strings that keep a lone surrogate as a
+/// `Wtf8Buf` to `JsonHandler::wtf8_string` and `JsonHandler::wtf8_key`, and
+/// serializing one writes the surrogates back as `\u` escapes.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Wtf8Buf {
+    bytes: Vec<u8>,
+}
+
+/// A run of a `Wtf8Buf` that is valid UTF-8, or a lone surrogate.
+enum Chunk<'a> {
+    Str(&'a str),
+    Surrogate(u16),
+}
+
+impl Wtf8Buf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    /// Appends a lone surrogate. A high surrogate is never followed by a low one,
+    /// as the decoder combines those into a pair.
+    pub(crate) fn push_surrogate(&mut self, code: u16) {
+        self.bytes.extend_from_slice(&[
+            0xE0 | (code >> 12) as u8,
+            0x80 | (code >> 6 & 0x3F) as u8,
+            0x80 | (code & 0x3F) as u8,
+        ]);
+    }
+
+    /// Returns the WTF-8 encoded bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the string slice if there are no lone surrogates, otherwise returns None.
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(&self.bytes).ok()
+    }
+
+    /// Returns the string if there are no lone surrogates, otherwise returns `self`.
+    pub fn into_string(self) -> Result<String, Self> {
+        String::from_utf8(self.bytes).map_err(|e| Self {
+            bytes: e.into_bytes(),
+        })
+    }
+
+    /// Returns the string with each lone surrogate replaced by U+FFFD REPLACEMENT
+    /// CHARACTER.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        if let Some(s) = self.as_str() {
+            return Cow::Borrowed(s);
+        }
+        let mut output = String::with_capacity(self.bytes.len());
+        for chunk in self.chunks() {
+            match chunk {
+                Chunk::Str(s) => output.push_str(s),
+                Chunk::Surrogate(_) => output.push(char::REPLACEMENT_CHARACTER),
+            }
+        }
+        Cow::Owned(output)
+    }
+
+    fn chunks(&self) -> impl Iterator<Item = Chunk<'_>> {
+        let bytes = &self.bytes[..];
+        let mut start = 0;
+        core::iter::from_fn(move || {
+            let rest = &bytes[start..];
+            // UTF-8 only follows 0xED with 0x80..=0x9F, so a higher byte starts a surrogate.
+            let surrogate = rest
+                .windows(2)
+                .position(|pair| pair[0] == 0xED && pair[1] >= 0xA0);
+            match surrogate {
+                Some(0) => {
+                    start += 3;
+                    let code = 0xD000 | (rest[1] as u16 & 0x3F) << 6 | (rest[2] as u16 & 0x3F);
+                    Some(Chunk::Surrogate(code))
+                }
+                _ if rest.is_empty() => None,
+                end => {
+                    let end = end.unwrap_or(rest.len());
+                    start += end;
+                    // Runs between surrogates are valid UTF-8 by construction.
+                    Some(Chunk::Str(
+                        core::str::from_utf8(&rest[..end]).unwrap_or_default(),
+                    ))
+                }
+            }
+        })
+    }
+}
+
+impl From<&str> for Wtf8Buf {
+    fn from(value: &str) -> Self {
+        Self {
+            bytes: value.as_bytes().into(),
+        }
+    }
+}
+
+impl From<String> for Wtf8Buf {
+    fn from(value: String) -> Self {
+        Self {
+            bytes: value.into_bytes(),
+        }
+    }
+}
+
+/// Writes the string as JSON, with lone surrogates as `\u` escapes.
+impl Serialize for Wtf8Buf {
+    fn serialize_to(&self, buffer: &mut Vec<u8>, indent: u32, level: u32) {
+        self.serialize_to_with(buffer, &FormatOptions::pretty(indent), level);
+    }
+    fn serialize_to_with(&self, buffer: &mut Vec<u8>, options: &FormatOptions, level: u32) {
+        match self.write_to(buffer, options, level) {
+            Ok(()) => {}
+            Err(never) => match never {},
+        }
+    }
+    fn write_to<S: Sink>(
+        &self,
+        sink: &mut S,
+        options: &FormatOptions,
+        _level: u32,
+    ) -> Result<(), S::Error> {
+        sink.write_str("\"")?;
+        for chunk in self.chunks() {
+            match chunk {
+                Chunk::Str(s) => write_string_contents(sink, s, options)?,
+                Chunk::Surrogate(code) => write_unicode_escape(sink, code)?,
+            }
+        }
+        sink.write_str("\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_lone_surrogates() {
+        let mut value = Wtf8Buf::from("a");
+        value.push_surrogate(0xD83D);
+        value.push('é');
+        value.push_surrogate(0xDE00);
+        assert_eq!(
+            value.as_bytes(),
+            b"a\xED\xA0\xBD\xC3\xA9\xED\xB8\x80".as_ref()
+        );
+        assert_eq!(value.as_str(), None);
+        assert_eq!(value.to_string_lossy(), "a\u{FFFD}é\u{FFFD}");
+        assert_eq!(value.serialize(), r#""a\ud83dé\ude00""#.as_bytes());
+        assert_eq!(value.clone().into_string(), Err(value));
+
+        let value = Wtf8Buf::from("plain \u{D7FF}\u{E000}");
+        assert_eq!(value.as_str(), Some("plain \u{D7FF}\u{E000}"));
+        assert_eq!(value.serialize(), "\"plain \u{D7FF}\u{E000}\"".as_bytes());
+    }
+}